@@ -0,0 +1,115 @@
+use std::{
+	fs::{self, File},
+	io::Write,
+	net::IpAddr,
+	sync::Mutex,
+};
+
+use anyhow::Result;
+use jiff::Timestamp;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::Config, dirs, ext::PathExt, logger};
+
+lazy_static! {
+	static ref WRITER: Mutex<Writer> = Mutex::new(Writer::default());
+}
+
+/// Mutating request recorded in the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+	pub timestamp: Timestamp,
+	pub ip: Option<IpAddr>,
+	pub user_agent: Option<String>,
+	/// Who made the request, like `password`, `session` or `token:<name>`
+	pub identity: String,
+	pub method: String,
+	pub path: String,
+	/// Query and body parameters of the request in order with secrets redacted
+	pub params: Vec<(String, String)>,
+	/// Status code of the response
+	pub status: u16,
+}
+
+#[derive(Default)]
+struct Writer {
+	file: Option<File>,
+	size: usize,
+}
+
+/// Appends the entry as a JSON line to the current audit log file, which rotates like the other logs
+pub fn append(entry: &Entry) -> Result<()> {
+	let line = serde_json::to_string(entry)?;
+	let mut writer = WRITER.lock().unwrap();
+
+	let file = match &mut writer.file {
+		Some(file) => file,
+		None => writer.file.insert(logger::create_file(&dirs::audit())?),
+	};
+
+	writeln!(file, "{line}")?;
+	file.flush()?;
+
+	writer.size += line.len() + 1;
+
+	if writer.size > Config::new().log_size_limit * 1024 * 1024 {
+		writer.file = None;
+		writer.size = 0;
+	}
+
+	Ok(())
+}
+
+/// Returns all entries of the audit log from oldest to newest, skipping lines that cannot be parsed
+pub fn read() -> Result<Vec<Entry>> {
+	let path = dirs::audit();
+
+	if !path.exists() {
+		return Ok(Vec::new());
+	}
+
+	let mut files = fs::read_dir(path)?
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.collect::<Vec<_>>();
+
+	files.sort_by(|a, b| a.get_stem().cmp(b.get_stem()));
+
+	let entries = files
+		.iter()
+		.filter_map(|file| fs::read_to_string(file).ok())
+		.flat_map(|contents| {
+			contents
+				.lines()
+				.filter_map(|line| serde_json::from_str(line).ok())
+				.collect::<Vec<_>>()
+		})
+		.collect();
+
+	Ok(entries)
+}
+
+/// Returns true if the parameter holds a secret that must not be written to the audit log
+pub fn is_secret(key: &str) -> bool {
+	let key = key.to_ascii_lowercase();
+	["password", "token", "secret"]
+		.iter()
+		.any(|secret| key.contains(secret))
+}
+
+/// Redacts the value if the parameter is a secret or redacts the secret pairs of `key=value` lists,
+/// which are used to update settings
+pub fn redact(key: &str, value: &str) -> String {
+	if is_secret(key) {
+		return String::from("********");
+	}
+
+	value
+		.split(',')
+		.map(|pair| match pair.split_once('=') {
+			Some((key, value)) if is_secret(key) && !value.is_empty() => format!("{key}=********"),
+			_ => pair.to_owned(),
+		})
+		.collect::<Vec<_>>()
+		.join(",")
+}
@@ -0,0 +1,510 @@
+use std::{
+	fmt::{self, Display, Formatter},
+	num::NonZeroU32,
+	str::FromStr,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error, Result, bail};
+use aws_lc_rs::{
+	constant_time,
+	digest::{self, SHA256},
+	hmac, pbkdf2,
+	rand::{SecureRandom, SystemRandom},
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// Scheme of the `Authorization` header used for signed requests
+pub const SCHEME: &str = "Racky-HMAC";
+/// Maximum difference between the client and server clocks for a signed request to be accepted
+pub const MAX_CLOCK_SKEW: Duration = Duration::from_secs(5 * 60);
+
+const HASH_ALGORITHM: &str = "scram-sha256";
+const HASH_ITERATIONS: u32 = 100_000;
+const HASH_LENGTH: usize = 32;
+const SALT_LENGTH: usize = 16;
+
+/// Verifier of the server password, stored as `scram-sha256$<iterations>$<salt>$<stored key>`
+///
+/// Like in SCRAM, the client key is derived from the salted password and only its hash, the stored key, is kept.
+/// Requests are signed with the stored key and carry the client key masked by that signature,
+/// so whoever reads the verifier still cannot sign requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordHash {
+	pub params: HashParams,
+	stored_key: Vec<u8>,
+}
+
+impl PasswordHash {
+	pub fn new(password: &str) -> Self {
+		let mut salt = vec![0; SALT_LENGTH];
+		SystemRandom::new().fill(&mut salt).expect("Failed to generate salt");
+
+		let params = HashParams {
+			iterations: HASH_ITERATIONS,
+			salt,
+		};
+
+		Self {
+			stored_key: stored_key(&params.client_key(password)),
+			params,
+		}
+	}
+
+	/// Returns true if the given string is a password hash rather than a plain password
+	pub fn is_hash(value: &str) -> bool {
+		value.starts_with(&format!("{HASH_ALGORITHM}$"))
+	}
+
+	pub fn verify(&self, password: &str) -> bool {
+		constant_time::verify_slices_are_equal(&stored_key(&self.params.client_key(password)), &self.stored_key).is_ok()
+	}
+
+	/// Returns the key signatures are verified with
+	pub fn stored_key(&self) -> &[u8] {
+		&self.stored_key
+	}
+}
+
+impl Display for PasswordHash {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}${}", self.params, STANDARD.encode(&self.stored_key))
+	}
+}
+
+impl FromStr for PasswordHash {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let (params, stored_key) = s.rsplit_once('$').context("Password hash is missing its value")?;
+
+		Ok(Self {
+			params: params.parse()?,
+			stored_key: STANDARD
+				.decode(stored_key)
+				.context("Password hash is not valid base64")?,
+		})
+	}
+}
+
+/// Public parameters of a password hash that clients need to derive their key
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashParams {
+	iterations: u32,
+	salt: Vec<u8>,
+}
+
+impl HashParams {
+	/// Derives the client key from the given password, which only the client knows
+	pub fn client_key(&self, password: &str) -> Vec<u8> {
+		let mut salted = vec![0; HASH_LENGTH];
+		let iterations = NonZeroU32::new(self.iterations).unwrap_or(NonZeroU32::MIN);
+
+		pbkdf2::derive(
+			pbkdf2::PBKDF2_HMAC_SHA256,
+			iterations,
+			&self.salt,
+			password.as_bytes(),
+			&mut salted,
+		);
+
+		client_key(&salted)
+	}
+}
+
+impl Display for HashParams {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{HASH_ALGORITHM}${}${}",
+			self.iterations,
+			STANDARD.encode(&self.salt)
+		)
+	}
+}
+
+impl FromStr for HashParams {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let mut parts = s.split('$');
+
+		if parts.next() != Some(HASH_ALGORITHM) {
+			bail!("Unsupported password hash algorithm, expected {HASH_ALGORITHM}");
+		}
+
+		let iterations = parts
+			.next()
+			.and_then(|iterations| iterations.parse().ok())
+			.context("Password hash has invalid iterations")?;
+
+		let salt = parts
+			.next()
+			.and_then(|salt| STANDARD.decode(salt).ok())
+			.context("Password hash has invalid salt")?;
+
+		if parts.next().is_some() {
+			bail!("Password hash parameters have too many parts");
+		}
+
+		Ok(Self { iterations, salt })
+	}
+}
+
+/// Signature of a single request, sent as `Racky-HMAC [token=<id>,]timestamp=<secs>,nonce=<hex>,proof=<hex>`
+///
+/// The proof is the client key XORed with the HMAC of the request made with the stored key,
+/// the server recovers the client key from it and checks that it hashes to the stored key
+#[derive(Debug)]
+pub struct Signature {
+	/// ID of the API token the request is signed with, none if signed with the server password
+	pub token: Option<String>,
+	pub timestamp: u64,
+	pub nonce: String,
+	proof: String,
+}
+
+impl Signature {
+	/// Signs a request with the given method, path including the query, content type and body
+	pub fn new(client_key: &[u8], token: Option<&str>, request: &SignedRequest) -> Self {
+		let timestamp = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+
+		Self::sign(client_key, token, request, timestamp)
+	}
+
+	fn sign(client_key: &[u8], token: Option<&str>, request: &SignedRequest, timestamp: u64) -> Self {
+		let mut nonce = [0; 16];
+		SystemRandom::new().fill(&mut nonce).expect("Failed to generate nonce");
+		let nonce = to_hex(&nonce);
+
+		let key = hmac::Key::new(hmac::HMAC_SHA256, &stored_key(client_key));
+		let signature = hmac::sign(&key, request.message(timestamp, &nonce).as_bytes());
+
+		Self {
+			proof: to_hex(&xor(client_key, signature.as_ref())),
+			token: token.map(str::to_owned),
+			timestamp,
+			nonce,
+		}
+	}
+
+	/// Returns true if the signature proves knowledge of the client key behind the given stored key
+	/// for this request and is recent enough
+	pub fn verify(&self, stored_key: &[u8], request: &SignedRequest) -> bool {
+		let now = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.unwrap_or_default()
+			.as_secs();
+
+		if now.abs_diff(self.timestamp) > MAX_CLOCK_SKEW.as_secs() {
+			return false;
+		}
+
+		let Some(proof) = from_hex(&self.proof).filter(|proof| proof.len() == HASH_LENGTH) else {
+			return false;
+		};
+
+		let key = hmac::Key::new(hmac::HMAC_SHA256, stored_key);
+		let signature = hmac::sign(&key, request.message(self.timestamp, &self.nonce).as_bytes());
+		let client_key = xor(&proof, signature.as_ref());
+
+		constant_time::verify_slices_are_equal(&self::stored_key(&client_key), stored_key).is_ok()
+	}
+}
+
+/// Parts of a request covered by its signature
+#[derive(Debug)]
+pub struct SignedRequest<'a> {
+	pub method: &'a str,
+	/// Path including the query
+	pub path: &'a str,
+	pub content_type: &'a str,
+	pub body: &'a [u8],
+}
+
+impl SignedRequest<'_> {
+	fn message(&self, timestamp: u64, nonce: &str) -> String {
+		let body = to_hex(digest::digest(&SHA256, self.body).as_ref());
+
+		format!(
+			"{}\n{}\n{timestamp}\n{nonce}\n{}\n{body}",
+			self.method, self.path, self.content_type
+		)
+	}
+}
+
+impl Display for Signature {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{SCHEME} ")?;
+
+		if let Some(token) = &self.token {
+			write!(f, "token={token},")?;
+		}
+
+		write!(
+			f,
+			"timestamp={},nonce={},proof={}",
+			self.timestamp, self.nonce, self.proof
+		)
+	}
+}
+
+impl FromStr for Signature {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		let fields = s
+			.strip_prefix(SCHEME)
+			.context("Authorization scheme is not supported")?
+			.trim()
+			.split(',')
+			.filter_map(|field| field.trim().split_once('='))
+			.collect::<Vec<_>>();
+
+		let get = |name: &str| {
+			fields
+				.iter()
+				.find(|(key, _)| *key == name)
+				.map(|(_, value)| value.to_string())
+				.with_context(|| format!("Signature is missing `{name}`"))
+		};
+
+		Ok(Self {
+			token: get("token").ok(),
+			timestamp: get("timestamp")?.parse().context("Signature has invalid timestamp")?,
+			nonce: get("nonce")?,
+			proof: get("proof")?,
+		})
+	}
+}
+
+/// Derives the client key from a salted password or another secret
+pub(crate) fn client_key(secret: &[u8]) -> Vec<u8> {
+	let key = hmac::Key::new(hmac::HMAC_SHA256, secret);
+	hmac::sign(&key, b"Client Key").as_ref().to_vec()
+}
+
+/// Returns the hash of a client key, which is all the server stores
+pub(crate) fn stored_key(client_key: &[u8]) -> Vec<u8> {
+	digest::digest(&SHA256, client_key).as_ref().to_vec()
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+	a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Option<Vec<u8>> {
+	if !hex.len().is_multiple_of(2) {
+		return None;
+	}
+
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn request<'a>(body: &'a [u8]) -> SignedRequest<'a> {
+		SignedRequest {
+			method: "POST",
+			path: "/api/programs/web/start?force=true",
+			content_type: "application/x-www-form-urlencoded",
+			body,
+		}
+	}
+
+	fn hash() -> PasswordHash {
+		let params = HashParams {
+			iterations: 1000,
+			salt: b"0123456789abcdef".to_vec(),
+		};
+
+		PasswordHash {
+			stored_key: stored_key(&params.client_key("secret")),
+			params,
+		}
+	}
+
+	#[test]
+	fn hash_round_trip() {
+		let hash = PasswordHash::new("secret");
+		let parsed = hash.to_string().parse::<PasswordHash>().unwrap();
+
+		assert!(PasswordHash::is_hash(&hash.to_string()));
+		assert!(hash.to_string().starts_with("scram-sha256$100000$"));
+		assert_eq!(parsed, hash);
+		assert!(parsed.verify("secret"));
+		assert!(!parsed.verify("Secret"));
+		assert!(!parsed.verify(""));
+	}
+
+	#[test]
+	fn hashes_are_salted() {
+		assert_ne!(PasswordHash::new("secret"), PasswordHash::new("secret"));
+	}
+
+	#[test]
+	fn plain_passwords_are_not_hashes() {
+		assert!(!PasswordHash::is_hash("secret"));
+		assert!(!PasswordHash::is_hash("scram-sha256"));
+		assert!(!PasswordHash::is_hash("racky_0123_abcd"));
+	}
+
+	#[test]
+	fn parse_hash_params() {
+		let params = hash().params;
+		assert_eq!(params.to_string().parse::<HashParams>().unwrap(), params);
+
+		assert!("md5$1000$c2FsdA==".parse::<HashParams>().is_err());
+		assert!("scram-sha256$many$c2FsdA==".parse::<HashParams>().is_err());
+		assert!("scram-sha256$1000$not base64".parse::<HashParams>().is_err());
+		assert!("scram-sha256$1000".parse::<HashParams>().is_err());
+		assert!("scram-sha256$1000$c2FsdA==$c2FsdA==".parse::<HashParams>().is_err());
+	}
+
+	#[test]
+	fn parse_invalid_hash() {
+		assert!("scram-sha256$1000$c2FsdA==".parse::<PasswordHash>().is_err());
+		assert!("scram-sha256$1000$c2FsdA==$not base64".parse::<PasswordHash>().is_err());
+		assert!("secret".parse::<PasswordHash>().is_err());
+	}
+
+	#[test]
+	fn client_key_depends_on_password() {
+		let params = hash().params;
+		assert_eq!(params.client_key("secret"), params.client_key("secret"));
+		assert_ne!(params.client_key("secret"), params.client_key("other"));
+	}
+
+	#[test]
+	fn parse_signature() {
+		let hash = hash();
+		let client_key = hash.params.client_key("secret");
+		let signature = Signature::new(&client_key, Some("0123"), &request(b"a=1"));
+		let header = signature.to_string();
+
+		assert!(header.starts_with("Racky-HMAC token=0123,timestamp="));
+
+		let parsed = header.parse::<Signature>().unwrap();
+		assert_eq!(parsed.token.as_deref(), Some("0123"));
+		assert_eq!(parsed.timestamp, signature.timestamp);
+		assert_eq!(parsed.nonce, signature.nonce);
+		assert!(parsed.verify(hash.stored_key(), &request(b"a=1")));
+
+		let parsed = Signature::new(&client_key, None, &request(b""))
+			.to_string()
+			.parse::<Signature>()
+			.unwrap();
+		assert!(parsed.token.is_none());
+
+		assert!("Bearer abc".parse::<Signature>().is_err());
+		assert!("Racky-HMAC timestamp=1,nonce=ab".parse::<Signature>().is_err());
+		assert!(
+			"Racky-HMAC timestamp=now,nonce=ab,proof=cd"
+				.parse::<Signature>()
+				.is_err()
+		);
+	}
+
+	#[test]
+	fn verify_signature() {
+		let hash = hash();
+		let signature = Signature::new(&hash.params.client_key("secret"), None, &request(b"a=1"));
+
+		assert!(signature.verify(hash.stored_key(), &request(b"a=1")));
+	}
+
+	#[test]
+	fn tampered_signature_is_rejected() {
+		let hash = hash();
+		let signature = Signature::new(&hash.params.client_key("secret"), None, &request(b"a=1"));
+		let stored_key = hash.stored_key();
+
+		assert!(!signature.verify(stored_key, &request(b"a=2")));
+		assert!(!signature.verify(stored_key, &request(b"")));
+
+		for tampered in [
+			SignedRequest {
+				method: "GET",
+				..request(b"a=1")
+			},
+			SignedRequest {
+				path: "/api/programs/web/stop?force=true",
+				..request(b"a=1")
+			},
+			SignedRequest {
+				content_type: "multipart/form-data",
+				..request(b"a=1")
+			},
+		] {
+			assert!(!signature.verify(stored_key, &tampered));
+		}
+
+		let mut proof = from_hex(&signature.proof).unwrap();
+		proof[0] ^= 1;
+		let forged = Signature {
+			proof: to_hex(&proof),
+			..signature
+		};
+		assert!(!forged.verify(stored_key, &request(b"a=1")));
+
+		let truncated = Signature {
+			proof: forged.proof[..16].to_owned(),
+			..forged
+		};
+		assert!(!truncated.verify(stored_key, &request(b"a=1")));
+	}
+
+	#[test]
+	fn wrong_password_is_rejected() {
+		let hash = hash();
+		let signature = Signature::new(&hash.params.client_key("other"), None, &request(b""));
+
+		assert!(!signature.verify(hash.stored_key(), &request(b"")));
+	}
+
+	#[test]
+	fn stored_key_cannot_sign() {
+		let hash = hash();
+		let signature = Signature::new(hash.stored_key(), None, &request(b""));
+
+		assert!(!signature.verify(hash.stored_key(), &request(b"")));
+	}
+
+	#[test]
+	fn expired_signature_is_rejected() {
+		let hash = hash();
+		let client_key = hash.params.client_key("secret");
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+		let skew = MAX_CLOCK_SKEW.as_secs();
+
+		for (timestamp, valid) in [
+			(now - skew + 10, true),
+			(now + skew - 10, true),
+			(now - skew - 10, false),
+			(now + skew + 10, false),
+		] {
+			let signature = Signature::sign(&client_key, None, &request(b""), timestamp);
+			assert_eq!(signature.verify(hash.stored_key(), &request(b"")), valid);
+		}
+	}
+
+	#[test]
+	fn hex_round_trip() {
+		assert_eq!(to_hex(&[0x00, 0x7f, 0xff]), "007fff");
+		assert_eq!(from_hex("007fff"), Some(vec![0x00, 0x7f, 0xff]));
+		assert_eq!(from_hex("007"), None);
+		assert_eq!(from_hex("zz"), None);
+	}
+}
@@ -5,10 +5,10 @@ use clap::Parser;
 use colored::Colorize;
 
 use crate::{
-	client::Client,
+	client,
 	core::program::Paths,
 	ext::{PathExt, ResultExt},
-	servers, zip,
+	zip,
 };
 
 /// Add a new program to the server
@@ -17,12 +17,15 @@ pub struct Add {
 	/// Path to program file or directory
 	#[arg()]
 	path: PathBuf,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 	/// Start the program automatically
 	#[arg(short, long)]
 	auto_start: bool,
+	/// Arguments to pass to the program (after `--`)
+	#[arg(last = true)]
+	args: Vec<String>,
 }
 
 impl Add {
@@ -39,10 +42,15 @@ impl Add {
 			path.to_string().bold()
 		);
 
-		Client::new(&servers::get(self.server)?)
-			.binary("file", zip::compress(&path).desc("Failed to zip program")?)
-			.text("auto_start", self.auto_start)
-			.post("program/add")?
-			.handle()
+		let zip = zip::compress(&path).desc("Failed to zip program")?;
+		let args = shell_words::join(self.args);
+
+		client::run(self.server, "", |client| {
+			client
+				.binary("file", &zip)
+				.text("auto_start", self.auto_start)
+				.text("args", &args)
+				.post("program/add")
+		})
 	}
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Update program configuration
 #[derive(Parser)]
@@ -12,7 +12,7 @@ pub struct Config {
 	/// Key=Value pairs to update
 	#[arg()]
 	data: Vec<String>,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 	/// Restore all settings to their default values
@@ -29,12 +29,13 @@ impl Config {
 	}
 
 	fn config(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("program", self.program)
-			.text("data", self.data.join(","))
-			.text("default", self.default)
-			.text("list", self.list)
-			.post("program/config")?
-			.handle()
+		client::run(self.server, "", |client| {
+			client
+				.text("program", &self.program)
+				.texts("data", &self.data)
+				.text("default", self.default)
+				.text("list", self.list)
+				.post("program/config")
+		})
 	}
 }
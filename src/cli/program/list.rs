@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// List all programs on the server
 #[derive(Parser)]
 pub struct List {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -17,9 +17,6 @@ impl List {
 	}
 
 	fn list(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.get("program/list")?
-			.with_prefix("Program list:\n")
-			.handle()
+		client::run(self.server, "Program list:\n", |client| client.get("program/list"))
 	}
 }
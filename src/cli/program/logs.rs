@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Get logs of a program from the server
 #[derive(Parser)]
@@ -9,12 +9,15 @@ pub struct Logs {
 	/// Name of the program to get logs for
 	#[arg()]
 	program: String,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 	/// Page number (higher values mean older logs)
-	#[arg(short, long)]
+	#[arg(short, long, conflicts_with = "follow")]
 	page: Option<usize>,
+	/// Print new logs as they are written until interrupted
+	#[arg(short, long)]
+	follow: bool,
 }
 
 impl Logs {
@@ -23,11 +26,20 @@ impl Logs {
 	}
 
 	fn logs(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("program", self.program)
-			.text("page", self.page.unwrap_or_default())
-			.get("program/logs")?
-			.with_prefix("Program logs:\n")
-			.handle()
+		if self.follow {
+			return client::follow(
+				self.server,
+				"Program logs:",
+				|client| client.text("program", &self.program).stream("program/logs/follow"),
+				Ok,
+			);
+		}
+
+		client::run(self.server, "Program logs:\n", |client| {
+			client
+				.text("program", &self.program)
+				.text("page", self.page.unwrap_or_default())
+				.get("program/logs")
+		})
 	}
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Restart a program on the server
 #[derive(Parser)]
@@ -9,7 +9,7 @@ pub struct Restart {
 	/// Name of the program to restart
 	#[arg()]
 	program: String,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -20,9 +20,8 @@ impl Restart {
 	}
 
 	fn restart(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("program", self.program)
-			.post("program/restart")?
-			.handle()
+		client::run(self.server, "", |client| {
+			client.text("program", &self.program).post("program/restart")
+		})
 	}
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Get the status of a program on the server
 #[derive(Parser)]
@@ -9,7 +9,7 @@ pub struct Status {
 	/// Name of the program to get the status of
 	#[arg()]
 	program: String,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -20,10 +20,8 @@ impl Status {
 	}
 
 	fn status(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("program", self.program)
-			.get("program/status")?
-			.with_prefix("Program status:\n")
-			.handle()
+		client::run(self.server, "Program status:\n", |client| {
+			client.text("program", &self.program).get("program/status")
+		})
 	}
 }
@@ -5,10 +5,10 @@ use clap::Parser;
 use colored::Colorize;
 
 use crate::{
-	client::Client,
+	client,
 	core::program::Paths,
 	ext::{PathExt, ResultExt},
-	servers, zip,
+	zip,
 };
 
 /// Update a program on the server
@@ -17,7 +17,7 @@ pub struct Update {
 	/// Path to program file or directory
 	#[arg()]
 	path: PathBuf,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -36,9 +36,10 @@ impl Update {
 			path.to_string().bold()
 		);
 
-		Client::new(&servers::get(self.server)?)
-			.binary("file", zip::compress(&path).desc("Failed to zip program")?)
-			.post("program/update")?
-			.handle()
+		let zip = zip::compress(&path).desc("Failed to zip program")?;
+
+		client::run(self.server, "", |client| {
+			client.binary("file", &zip).post("program/update")
+		})
 	}
 }
@@ -3,9 +3,10 @@ use clap::Parser;
 use colored::Colorize;
 
 use crate::{
+	client::Client,
 	config::Config,
 	ext::ResultExt,
-	racky_info,
+	logger, racky_info, racky_warn,
 	servers::{self, Server},
 };
 
@@ -24,6 +25,18 @@ pub struct Add {
 	/// Password for the server
 	#[arg(short, long)]
 	password: Option<String>,
+	/// Connect to the server over HTTPS
+	#[arg(short, long)]
+	tls: bool,
+	/// SHA-256 fingerprint of the server certificate to trust (fetched from the server when not provided)
+	#[arg(short, long, requires = "tls")]
+	fingerprint: Option<String>,
+	/// Tags to group the server by, so they can be used as a target for commands (can be repeated or comma-separated)
+	#[arg(short = 'T', long = "tag", value_delimiter = ',')]
+	tags: Vec<String>,
+	/// Send the plain password if the server does not support signed requests
+	#[arg(long)]
+	legacy_auth: bool,
 }
 
 impl Add {
@@ -38,6 +51,8 @@ impl Add {
 		let port = self.port.unwrap_or(config.port);
 		let password = self.password.unwrap_or(config.password);
 
+		servers::validate_alias(&self.server)?;
+
 		let mut servers = servers::read()?;
 
 		ensure!(
@@ -53,24 +68,57 @@ impl Add {
 			port.to_string().bold()
 		);
 
-		servers.insert(
-			self.server.clone(),
-			Server {
-				address: address.clone(),
-				port,
-				password,
-				default: !servers.values().any(|s| s.default),
-			},
-		);
+		for tag in &self.tags {
+			servers::validate_tag(tag)?;
+		}
+
+		let mut server = Server {
+			address,
+			port,
+			password,
+			tls: self.tls,
+			fingerprint: self.fingerprint.unwrap_or_default(),
+			tags: self.tags.into_iter().collect(),
+			legacy_auth: self.legacy_auth,
+			default: !servers.values().any(|s| s.default),
+		};
+
+		if server.tls && server.fingerprint.is_empty() {
+			pin_fingerprint(&self.server, &mut server);
+		}
 
+		let url = server.url();
+
+		servers.insert(self.server.clone(), server);
 		servers::write(&servers)?;
 
 		racky_info!(
 			"Server {} with URL {} added successfully",
 			self.server.bold(),
-			format!("http://{address}:{port}").bold()
+			url.bold()
 		);
 
 		Ok(())
 	}
 }
+
+/// Fetches the certificate fingerprint of the server and pins it if the user trusts it
+pub fn pin_fingerprint(alias: &str, server: &mut Server) {
+	match Client::new(alias, server).fetch_fingerprint() {
+		Ok(fingerprint) => {
+			racky_info!("Server certificate fingerprint (SHA-256): {}", fingerprint.bold());
+
+			if logger::prompt(
+				"Does it match the fingerprint printed by the server on start? Do you want to trust it?",
+				true,
+			) {
+				server.fingerprint = fingerprint;
+			} else {
+				racky_warn!("Certificate was not pinned, it has to be signed by a trusted certificate authority");
+			}
+		}
+		Err(err) => racky_warn!(
+			"Could not get server certificate fingerprint: {err}. Pin it later with `racky server change --fingerprint`"
+		),
+	}
+}
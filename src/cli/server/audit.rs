@@ -0,0 +1,61 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{client, ext::ResultExt};
+
+/// Show the audit log of mutating requests handled by the server
+#[derive(Parser)]
+pub struct Audit {
+	/// Only show requests for this program
+	#[arg(short, long)]
+	program: Option<String>,
+	/// Only show requests with this action, like `restart` or `update`
+	#[arg(short, long)]
+	action: Option<String>,
+	/// Only show requests made with this identity, like `password`, `session` or an API token name
+	#[arg(short, long)]
+	identity: Option<String>,
+	/// Only show requests from this IP address
+	#[arg(long)]
+	ip: Option<String>,
+	/// Only show requests since this timestamp or for this duration, like `2h` or `7d`
+	#[arg(long)]
+	since: Option<String>,
+	/// Only show requests that failed
+	#[arg(short, long)]
+	failed: bool,
+	/// Maximum number of entries to show, starting from the newest
+	#[arg(short = 'n', long, default_value_t = 50)]
+	limit: usize,
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl Audit {
+	pub fn main(self) -> Result<()> {
+		self.audit().desc("Failed to get server audit log")
+	}
+
+	fn audit(self) -> Result<()> {
+		let filters = [
+			("program", self.program),
+			("action", self.action),
+			("identity", self.identity),
+			("ip", self.ip),
+			("since", self.since),
+		];
+
+		client::run(self.server, "Audit log:\n", |client| {
+			let mut client = client.text("failed", self.failed).text("limit", self.limit);
+
+			for (key, value) in &filters {
+				if let Some(value) = value {
+					client = client.text(*key, value);
+				}
+			}
+
+			client.get("server/audit")
+		})
+	}
+}
@@ -2,7 +2,7 @@ use anyhow::{Result, bail, ensure};
 use clap::Parser;
 use colored::Colorize;
 
-use crate::{ext::ResultExt, racky_info, servers};
+use crate::{cli::server::add::pin_fingerprint, ext::ResultExt, racky_info, servers};
 
 /// Change details of a saved server
 #[derive(Parser)]
@@ -22,6 +22,21 @@ pub struct Change {
 	/// New password for the server
 	#[arg(short, long)]
 	password: Option<String>,
+	/// Whether to connect to the server over HTTPS
+	#[arg(short, long)]
+	tls: Option<bool>,
+	/// New SHA-256 fingerprint of the server certificate to trust (empty to fetch it from the server again)
+	#[arg(short, long)]
+	fingerprint: Option<String>,
+	/// Tags to add to the server (can be repeated or comma-separated)
+	#[arg(short = 'T', long = "tag", value_delimiter = ',')]
+	tags: Vec<String>,
+	/// Tags to remove from the server (can be repeated or comma-separated)
+	#[arg(short = 'U', long = "untag", value_delimiter = ',')]
+	untags: Vec<String>,
+	/// Whether to send the plain password if the server does not support signed requests
+	#[arg(long)]
+	legacy_auth: Option<bool>,
 	/// Set the server as the default
 	#[arg(short, long)]
 	default: Option<bool>,
@@ -42,6 +57,21 @@ impl Change {
 
 		let mut updated = false;
 
+		let alias = match self.alias {
+			Some(alias) if alias != self.server => {
+				servers::validate_alias(&alias)?;
+				ensure!(
+					!servers.contains_key(&alias),
+					"Server with alias {} already exists",
+					alias.bold()
+				);
+
+				updated = true;
+				alias
+			}
+			_ => self.server.clone(),
+		};
+
 		if let Some(address) = self.address
 			&& address != server.address
 		{
@@ -63,6 +93,43 @@ impl Change {
 			updated = true;
 		}
 
+		let mut fetch_fingerprint = false;
+
+		if let Some(tls) = self.tls
+			&& tls != server.tls
+		{
+			server.tls = tls;
+			server.fingerprint.clear();
+			fetch_fingerprint = tls;
+			updated = true;
+		}
+
+		if let Some(fingerprint) = self.fingerprint {
+			fetch_fingerprint = fingerprint.is_empty();
+			server.fingerprint = fingerprint;
+			updated = true;
+		}
+
+		if fetch_fingerprint && server.tls {
+			pin_fingerprint(&self.server, &mut server);
+		}
+
+		for tag in self.tags {
+			servers::validate_tag(&tag)?;
+			updated |= server.tags.insert(tag);
+		}
+
+		for tag in self.untags {
+			updated |= server.tags.remove(&tag);
+		}
+
+		if let Some(legacy_auth) = self.legacy_auth
+			&& legacy_auth != server.legacy_auth
+		{
+			server.legacy_auth = legacy_auth;
+			updated = true;
+		}
+
 		if let Some(default) = self.default
 			&& default != server.default
 		{
@@ -74,13 +141,6 @@ impl Change {
 			}
 		};
 
-		let alias = if let Some(alias) = self.alias {
-			updated = true;
-			alias
-		} else {
-			self.server
-		};
-
 		ensure!(updated, "No changes detected");
 
 		servers.insert(alias.clone(), server);
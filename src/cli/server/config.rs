@@ -1,7 +1,7 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Update server configuration
 #[derive(Parser)]
@@ -9,7 +9,7 @@ pub struct Config {
 	/// Key=Value pairs to update
 	#[arg()]
 	data: Vec<String>,
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 	/// Restore all settings to their default values
@@ -26,11 +26,12 @@ impl Config {
 	}
 
 	fn config(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("list", self.list)
-			.text("default", self.default)
-			.text("data", self.data.join(","))
-			.post("server/config")?
-			.handle()
+		client::run(self.server, "", |client| {
+			client
+				.text("list", self.list)
+				.text("default", self.default)
+				.text("data", self.data.join(","))
+				.post("server/config")
+		})
 	}
 }
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{client, core::event::Event, ext::ResultExt};
+
+/// Print program status changes on the server as they happen
+#[derive(Parser)]
+pub struct Events {
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl Events {
+	pub fn main(self) -> Result<()> {
+		self.events().desc("Failed to get server events")
+	}
+
+	fn events(self) -> Result<()> {
+		client::follow(
+			self.server,
+			"Server events:",
+			|client| client.stream("server/events"),
+			|data| {
+				let event = serde_json::from_str::<Event>(&data).desc("Failed to parse server event")?;
+				Ok(event.to_string())
+			},
+		)
+	}
+}
@@ -5,7 +5,11 @@ use crate::{ext::ResultExt, logger::Table, racky_info, servers};
 
 /// List all saved servers
 #[derive(Parser)]
-pub struct List {}
+pub struct List {
+	/// Only list servers with any of these tags (can be repeated or comma-separated)
+	#[arg(short = 'T', long = "tag", value_delimiter = ',')]
+	tags: Vec<String>,
+}
 
 impl List {
 	pub fn main(self) -> Result<()> {
@@ -13,12 +17,21 @@ impl List {
 	}
 
 	fn list(self) -> Result<()> {
-		let servers = servers::read()?;
+		let mut servers = servers::read()?
+			.into_iter()
+			.filter(|(_, server)| self.tags.is_empty() || self.tags.iter().any(|tag| server.tags.contains(tag)))
+			.collect::<Vec<_>>();
+
+		ensure!(
+			!servers.is_empty(),
+			"There are no saved Racky servers{}",
+			if self.tags.is_empty() { "" } else { " with these tags" }
+		);
 
-		ensure!(!servers.is_empty(), "There are no saved Racky servers");
+		servers.sort_by(|a, b| a.0.cmp(&b.0));
 
 		let mut table = Table::new();
-		table.set_header(vec!["Alias", "Address", "Port", "Password", "Default"]);
+		table.set_header(vec!["Alias", "Address", "Port", "Password", "TLS", "Tags", "Default"]);
 
 		for (alias, server) in servers {
 			table.add_row(vec![
@@ -26,6 +39,12 @@ impl List {
 				server.address,
 				server.port.to_string(),
 				server.password,
+				match (server.tls, server.fingerprint.is_empty()) {
+					(false, _) => String::from("false"),
+					(true, true) => String::from("true"),
+					(true, false) => String::from("true (pinned)"),
+				},
+				server.tags.into_iter().collect::<Vec<_>>().join(", "),
 				server.default.to_string(),
 			]);
 		}
@@ -1,17 +1,20 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Get logs from the server
 #[derive(Parser)]
 pub struct Logs {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 	/// Page number (higher values mean older logs)
-	#[arg(short, long)]
+	#[arg(short, long, conflicts_with = "follow")]
 	page: Option<usize>,
+	/// Print new logs as they are written until interrupted
+	#[arg(short, long)]
+	follow: bool,
 }
 
 impl Logs {
@@ -20,10 +23,17 @@ impl Logs {
 	}
 
 	fn logs(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.text("page", self.page.unwrap_or_default())
-			.get("server/logs")?
-			.with_prefix("Server logs:\n")
-			.handle()
+		if self.follow {
+			return client::follow(
+				self.server,
+				"Server logs:",
+				|client| client.stream("server/logs/follow"),
+				Ok,
+			);
+		}
+
+		client::run(self.server, "Server logs:\n", |client| {
+			client.text("page", self.page.unwrap_or_default()).get("server/logs")
+		})
 	}
 }
@@ -2,10 +2,13 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 
 mod add;
+mod audit;
 mod change;
 mod config;
+mod events;
 mod list;
 mod logs;
+mod ping;
 mod reboot;
 mod remove;
 mod restart;
@@ -13,6 +16,7 @@ mod shutdown;
 mod start;
 mod status;
 mod stop;
+mod token;
 mod update;
 
 /// Manage and configure Racky servers
@@ -30,10 +34,13 @@ impl Server {
 	pub fn main(self) -> Result<()> {
 		match self.command {
 			Command::Add(command) => command.main(),
+			Command::Audit(command) => command.main(),
 			Command::Change(command) => command.main(),
 			Command::Config(command) => command.main(),
+			Command::Events(command) => command.main(),
 			Command::List(command) => command.main(),
 			Command::Logs(command) => command.main(),
+			Command::Ping(command) => command.main(),
 			Command::Reboot(command) => command.main(),
 			Command::Remove(command) => command.main(),
 			Command::Restart(command) => command.main(),
@@ -41,6 +48,7 @@ impl Server {
 			Command::Start(command) => command.main(),
 			Command::Status(command) => command.main(),
 			Command::Stop(command) => command.main(),
+			Command::Token(command) => command.main(),
 			Command::Update(command) => command.main(),
 		}
 	}
@@ -49,10 +57,13 @@ impl Server {
 #[derive(Subcommand)]
 enum Command {
 	Add(add::Add),
+	Audit(audit::Audit),
 	Change(change::Change),
 	Config(config::Config),
+	Events(events::Events),
 	List(list::List),
 	Logs(logs::Logs),
+	Ping(ping::Ping),
 	Reboot(reboot::Reboot),
 	Remove(remove::Remove),
 	Restart(restart::Restart),
@@ -60,5 +71,6 @@ enum Command {
 	Start(start::Start),
 	Status(status::Status),
 	Stop(stop::Stop),
+	Token(token::Token),
 	Update(update::Update),
 }
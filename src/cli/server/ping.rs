@@ -0,0 +1,39 @@
+use anyhow::Result;
+use clap::Parser;
+use reqwest::StatusCode;
+
+use crate::{
+	client::{self, Response},
+	ext::ResultExt,
+};
+
+/// Check that the server is reachable and show its latency and version
+#[derive(Parser)]
+pub struct Ping {
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl Ping {
+	pub fn main(self) -> Result<()> {
+		self.ping().desc("Failed to ping the server")
+	}
+
+	fn ping(self) -> Result<()> {
+		client::run(self.server, "", |client| {
+			let (latency, version) = client.ping()?;
+
+			let version = match version {
+				Some(version) if version == env!("CARGO_PKG_VERSION") => version,
+				Some(version) => format!("{version} (client is {})", env!("CARGO_PKG_VERSION")),
+				None => String::from("unknown (older than the client)"),
+			};
+
+			Ok(Response(
+				StatusCode::OK,
+				format!("Pong in {:.1}ms from Racky {version}", latency.as_secs_f64() * 1000.0),
+			))
+		})
+	}
+}
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, logger, servers};
+use crate::{client, ext::ResultExt, logger};
 
 /// Reboot the server (hardware)
 #[derive(Parser)]
 pub struct Reboot {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -24,6 +24,6 @@ impl Reboot {
 	}
 
 	fn reboot(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?).post("server/reboot")?.handle()
+		client::run(self.server, "", |client| client.post("server/reboot"))
 	}
 }
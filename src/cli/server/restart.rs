@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, logger, servers};
+use crate::{client, ext::ResultExt, logger};
 
 /// Restart the server (software)
 #[derive(Parser)]
 pub struct Restart {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -24,8 +24,6 @@ impl Restart {
 	}
 
 	fn restart(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.post("server/restart")?
-			.handle()
+		client::run(self.server, "", |client| client.post("server/restart"))
 	}
 }
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, logger, servers};
+use crate::{client, ext::ResultExt, logger};
 
 /// Shut down the server (hardware)
 #[derive(Parser)]
 pub struct Shutdown {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -24,8 +24,6 @@ impl Shutdown {
 	}
 
 	fn shutdown(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.post("server/shutdown")?
-			.handle()
+		client::run(self.server, "", |client| client.post("server/shutdown"))
 	}
 }
@@ -1,14 +1,20 @@
+use std::{collections::BTreeSet, sync::Arc};
+
 use anyhow::{Result, ensure};
 use clap::Parser;
 use colored::Colorize;
 use log::{trace, warn};
+use rustls::ServerConfig;
 
 use crate::{
+	auth::PasswordHash,
 	config::Config,
 	core::Core,
+	dirs,
 	ext::ResultExt,
 	racky_error, racky_info, racky_warn,
 	servers::{self, Server},
+	tls, tokens,
 	web::Web,
 };
 
@@ -36,17 +42,51 @@ impl Start {
 
 		let address = self.address.unwrap_or(config.address.clone());
 		let port = self.port.unwrap_or(config.port);
-		let password = self
-			.password
+		// Only a password given on the command line is saved for the local server, the config only has its hash
+		let password = self.password.filter(|p| !p.is_empty());
+
+		let hash = password
+			.clone()
 			.or(Some(config.password.clone()))
-			.filter(|p| !p.is_empty());
+			.filter(|p| !p.is_empty())
+			.map(|password| {
+				if PasswordHash::is_hash(&password) {
+					password
+						.parse()
+						.desc("Failed to read the password hash from the config")
+				} else {
+					Ok(PasswordHash::new(&password))
+				}
+			})
+			.transpose()?;
+
+		if config.legacy_auth {
+			racky_warn!("Legacy authentication is enabled, clients may send the plain password with every request");
+		}
 
 		let core = Core::new();
-		let web = Web::new(core.clone(), &address, port, password.clone());
+		let tokens = tokens::read()?;
+		let mut web = Web::new(core.clone(), &address, port, hash, tokens);
 
 		ensure!(web.is_port_free(), "Port {} is already in use", port.to_string().bold());
 
-		match Self::save_server(&address, port, password) {
+		let fingerprint = if config.tls {
+			let (tls_config, fingerprint) = Self::load_tls(&config)?;
+			web = web.with_tls(tls_config);
+
+			racky_info!("TLS certificate fingerprint (SHA-256): {}", fingerprint.bold());
+			Some(fingerprint)
+		} else {
+			None
+		};
+
+		if config.socket {
+			web = web.with_socket(dirs::socket());
+		}
+
+		let scheme = if fingerprint.is_some() { "https" } else { "http" };
+
+		match Self::save_server(&address, port, password, fingerprint) {
 			Ok(true) => trace!("Saved local server details"),
 			Err(err) => warn!("Failed to save local server: {err}"),
 			_ => (),
@@ -71,7 +111,7 @@ impl Start {
 
 		racky_info!(
 			"Racky server is running on {}",
-			format!("http://{address}:{port}").bold()
+			format!("{scheme}://{address}:{port}").bold()
 		);
 
 		drop(config);
@@ -80,19 +120,48 @@ impl Start {
 		web.serve().desc("Could not start the serve session")
 	}
 
-	fn save_server(address: &str, port: u16, password: Option<String>) -> Result<bool> {
+	fn load_tls(config: &Config) -> Result<(Arc<ServerConfig>, String)> {
+		let (cert, key) = tls::get_paths(&config.tls_cert, &config.tls_key);
+
+		if config.tls_generate && !cert.exists() && !key.exists() {
+			tls::generate(&cert, &key).desc("Failed to generate self-signed certificate")?;
+			racky_info!(
+				"Generated self-signed certificate {}",
+				cert.display().to_string().bold()
+			);
+		}
+
+		tls::server_config(&cert, &key).desc("Failed to load TLS certificate")
+	}
+
+	fn save_server(address: &str, port: u16, password: Option<String>, fingerprint: Option<String>) -> Result<bool> {
 		let mut servers = servers::read()?;
+		let tls = fingerprint.is_some();
+		let fingerprint = fingerprint.unwrap_or_default();
+
+		// Keep the local server reachable when HTTPS is turned on or off or the certificate changes
+		if let Some(server) = servers.get_mut(servers::LOCAL) {
+			if server.tls == tls && server.fingerprint == fingerprint {
+				return Ok(false);
+			}
+
+			server.tls = tls;
+			server.fingerprint = fingerprint;
+			servers::write(&servers)?;
 
-		if servers.contains_key("local") {
-			return Ok(false);
+			return Ok(true);
 		}
 
 		servers.insert(
-			String::from("local"),
+			String::from(servers::LOCAL),
 			Server {
 				address: address.to_owned(),
 				port,
 				password: password.unwrap_or_default(),
+				tls,
+				fingerprint,
+				tags: BTreeSet::new(),
+				legacy_auth: false,
 				default: !servers.values().any(|s| s.default),
 			},
 		);
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Get the status of the server
 #[derive(Parser)]
 pub struct Status {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -17,9 +17,6 @@ impl Status {
 	}
 
 	fn status(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?)
-			.get("server/status")?
-			.with_prefix("Server status:\n")
-			.handle()
+		client::run(self.server, "Server status:\n", |client| client.get("server/status"))
 	}
 }
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, logger, servers};
+use crate::{client, ext::ResultExt, logger};
 
 /// Stop the server (software)
 #[derive(Parser)]
 pub struct Stop {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -24,6 +24,6 @@ impl Stop {
 	}
 
 	fn stop(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?).post("server/stop")?.handle()
+		client::run(self.server, "", |client| client.post("server/stop"))
 	}
 }
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{client, ext::ResultExt, tokens::Scope};
+
+/// Create an API token, use it as the password when adding the server to another client
+#[derive(Parser)]
+pub struct Create {
+	/// Name of the token (must be unique)
+	#[arg()]
+	name: String,
+	/// Access granted to the token, each scope includes the previous ones
+	#[arg(short = 'S', long, value_enum, default_value_t = Scope::Read)]
+	scope: Scope,
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl Create {
+	pub fn main(self) -> Result<()> {
+		self.create().desc("Failed to create API token")
+	}
+
+	fn create(self) -> Result<()> {
+		client::run(self.server, "", |client| {
+			client
+				.text("name", &self.name)
+				.text("scope", self.scope.to_string())
+				.post("server/token/create")
+		})
+	}
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{client, ext::ResultExt};
+
+/// List all API tokens of the server
+#[derive(Parser)]
+pub struct List {
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl List {
+	pub fn main(self) -> Result<()> {
+		self.list().desc("Failed to list API tokens")
+	}
+
+	fn list(self) -> Result<()> {
+		client::run(self.server, "API tokens:\n", |client| client.get("server/token/list"))
+	}
+}
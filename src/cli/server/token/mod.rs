@@ -0,0 +1,30 @@
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+
+mod create;
+mod list;
+mod revoke;
+
+/// Manage scoped API tokens of the server
+#[derive(Parser)]
+pub struct Token {
+	#[command(subcommand)]
+	command: Command,
+}
+
+impl Token {
+	pub fn main(self) -> Result<()> {
+		match self.command {
+			Command::Create(command) => command.main(),
+			Command::List(command) => command.main(),
+			Command::Revoke(command) => command.main(),
+		}
+	}
+}
+
+#[derive(Subcommand)]
+enum Command {
+	Create(create::Create),
+	List(list::List),
+	Revoke(revoke::Revoke),
+}
@@ -0,0 +1,27 @@
+use anyhow::Result;
+use clap::Parser;
+
+use crate::{client, ext::ResultExt};
+
+/// Revoke an API token so it can no longer be used
+#[derive(Parser)]
+pub struct Revoke {
+	/// Name of the token to revoke
+	#[arg()]
+	name: String,
+	/// Target server alias or tag, a comma-separated list of them or `all`
+	#[arg(short, long)]
+	server: Option<String>,
+}
+
+impl Revoke {
+	pub fn main(self) -> Result<()> {
+		self.revoke().desc("Failed to revoke API token")
+	}
+
+	fn revoke(self) -> Result<()> {
+		client::run(self.server, "", |client| {
+			client.text("name", &self.name).post("server/token/revoke")
+		})
+	}
+}
@@ -1,12 +1,12 @@
 use anyhow::Result;
 use clap::Parser;
 
-use crate::{client::Client, ext::ResultExt, servers};
+use crate::{client, ext::ResultExt};
 
 /// Update the server to the latest version
 #[derive(Parser)]
 pub struct Update {
-	/// Target server alias
+	/// Target server alias or tag, a comma-separated list of them or `all`
 	#[arg(short, long)]
 	server: Option<String>,
 }
@@ -17,6 +17,6 @@ impl Update {
 	}
 
 	fn update(self) -> Result<()> {
-		Client::new(&servers::get(self.server)?).post("server/update")?.handle()
+		client::run(self.server, "", |client| client.post("server/update"))
 	}
 }
@@ -1,34 +1,72 @@
-use std::{borrow::Cow, collections::HashMap, fmt::Display};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	error,
+	fmt::{self, Display, Formatter},
+	io::{self, BufRead, BufReader, ErrorKind},
+	net::ToSocketAddrs,
+	path::PathBuf,
+	sync::OnceLock,
+	thread,
+	time::{Duration, Instant},
+};
 
-use anyhow::{Result, bail};
+use anyhow::{Context, Error, Result, anyhow, bail};
+use colored::Colorize;
+use log::warn;
 use reqwest::{
-	StatusCode,
+	Method, StatusCode, Url,
 	blocking::{
-		Client as ReqwestClient, RequestBuilder,
+		Body, Client as ReqwestClient, ClientBuilder, RequestBuilder, Response as ReqwestResponse,
 		multipart::{Form, Part},
 	},
 };
 
-use crate::{consts::USER_AGENT, ext::ResultExt, racky_info, servers::Server};
+use crate::{
+	auth::{HashParams, Signature, SignedRequest},
+	config::Config,
+	consts::{USER_AGENT, VERSION_HEADER},
+	dirs,
+	ext::ResultExt,
+	logger::Table,
+	racky_info, racky_warn,
+	servers::{self, Server},
+	tls, tokens,
+};
+
+/// Delay before the first retry of a failed request, doubled for each following one
+const RETRY_DELAY: Duration = Duration::from_millis(500);
 
 #[derive(Debug)]
 pub struct Client {
-	address: String,
-	port: u16,
+	url: String,
+	tls: bool,
+	fingerprint: Option<String>,
 	password: Option<String>,
+	/// Whether to send the plain password if the server does not support signed requests
+	legacy_auth: bool,
+	/// Unix socket of the server running on this machine, used instead of the network when available
+	socket: Option<PathBuf>,
+	credentials: OnceLock<Credentials>,
 	fields: HashMap<Cow<'static, str>, Field>,
 }
 
 impl Client {
-	pub fn new(server: &Server) -> Self {
+	pub fn new(alias: &str, server: &Server) -> Self {
 		Self {
-			address: server.address.clone(),
-			port: server.port,
+			url: server.url(),
+			tls: server.tls,
+			fingerprint: Some(server.fingerprint.clone()).filter(|fingerprint| !fingerprint.is_empty()),
 			password: if server.password.is_empty() {
 				None
 			} else {
 				Some(server.password.clone())
 			},
+			legacy_auth: server.legacy_auth,
+			socket: get_socket(alias, server),
+			credentials: OnceLock::new(),
 			fields: HashMap::new(),
 		}
 	}
@@ -42,6 +80,17 @@ impl Client {
 		self
 	}
 
+	/// Sends the field once for each of the values, in order
+	pub fn texts<K, V>(mut self, key: K, values: impl IntoIterator<Item = V>) -> Self
+	where
+		K: Into<Cow<'static, str>>,
+		V: Display,
+	{
+		let values = values.into_iter().map(|value| value.to_string()).collect();
+		self.fields.insert(key.into(), Field::Texts(values));
+		self
+	}
+
 	pub fn binary<K, V>(mut self, key: K, value: V) -> Self
 	where
 		K: Into<Cow<'static, str>>,
@@ -52,23 +101,36 @@ impl Client {
 	}
 
 	pub fn get(&self, path: &str) -> Result<Response> {
-		let mut request = ReqwestClient::builder()
+		let request = self
+			.builder()?
 			.build()
 			.desc("Failed to create HTTP GET client")?
 			.get(self.format_url(path));
 
-		for (key, value) in &self.fields {
-			match value {
-				Field::Text(value) => request = request.query(&[(key, value)]),
-				Field::Binary(_) => bail!("Binary fields are not supported for GET requests"),
-			}
+		self.send(self.with_query(request)?)
+	}
+
+	/// Sends a GET request to a server-sent events route, the returned iterator blocks until the next event arrives
+	pub fn stream(&self, path: &str) -> Result<Events> {
+		let request = self
+			.builder()?
+			.timeout(None)
+			.build()
+			.desc("Failed to create HTTP stream client")?
+			.get(self.format_url(path));
+
+		let response = self.check(self.execute(self.with_query(request)?, true)?)?;
+
+		if !response.status().is_success() {
+			return Err(Response(response.status(), response.text().unwrap_or_default()).into_error());
 		}
 
-		self.send(request)
+		Ok(Events(BufReader::new(response)))
 	}
 
 	pub fn post(&self, path: &str) -> Result<Response> {
-		let mut request = ReqwestClient::builder()
+		let mut request = self
+			.builder()?
 			.build()
 			.desc("Failed to create HTTP POST client")?
 			.post(self.format_url(path));
@@ -79,25 +141,26 @@ impl Client {
 			for (key, value) in &self.fields {
 				match value {
 					Field::Text(value) => form = form.text(key.clone(), value.clone()),
+					Field::Texts(values) => {
+						for value in values {
+							form = form.text(key.clone(), value.clone());
+						}
+					}
 					Field::Binary(value) => form = form.part(key.clone(), Part::bytes(value.clone())),
 				}
 			}
 
 			request = request.multipart(form);
 		} else if !self.fields.is_empty() {
-			let fields = self
-				.fields
-				.iter()
-				.map(|(key, value)| {
-					(
-						key.clone(),
-						match value {
-							Field::Text(value) => value.to_owned(),
-							Field::Binary(_) => unreachable!(),
-						},
-					)
-				})
-				.collect::<HashMap<_, _>>();
+			let mut fields = Vec::new();
+
+			for (key, value) in &self.fields {
+				match value {
+					Field::Text(value) => fields.push((key, value)),
+					Field::Texts(values) => fields.extend(values.iter().map(|value| (key, value))),
+					Field::Binary(_) => unreachable!(),
+				}
+			}
 
 			request = request.form(&fields);
 		}
@@ -105,22 +168,537 @@ impl Client {
 		self.send(request)
 	}
 
+	/// Sends a single ping request and returns its round trip time with the version the server reports, if any
+	pub fn ping(&self) -> Result<(Duration, Option<String>)> {
+		let url = self.format_url("ping");
+		let request = self
+			.builder()?
+			.build()
+			.desc("Failed to create HTTP GET client")?
+			.get(&url);
+
+		// Authentication parameters are fetched and the key derived beforehand so they are not measured
+		self.authorize(Method::GET.as_str(), &Url::parse(&url)?, "", &[])?;
+
+		let start = Instant::now();
+		let response = self.check(self.execute(request, false)?)?;
+		let latency = start.elapsed();
+
+		let version = get_version(&response);
+
+		if !response.status().is_success() {
+			return Err(Response(response.status(), response.text().unwrap_or_default()).into_error());
+		}
+
+		Ok((latency, version))
+	}
+
+	/// Connects to the server and returns the fingerprint of its certificate without verifying it
+	pub fn fetch_fingerprint(&self) -> Result<String> {
+		let (config, seen) = tls::capturing_config()?;
+
+		ReqwestClient::builder()
+			.tls_backend_preconfigured(config)
+			.build()
+			.desc("Failed to create HTTPS client")?
+			.get(format!("{}/ping", self.url))
+			.header("User-Agent", USER_AGENT)
+			.send()
+			.map_err(|err| self.classify(err))?;
+
+		seen.lock()
+			.unwrap()
+			.take()
+			.context("Server did not present a certificate")
+	}
+
+	/// Returns a client builder that trusts the pinned certificate of the server if there is one
+	fn builder(&self) -> Result<ClientBuilder> {
+		let config = Config::new();
+		let timeout = |secs: usize| (secs != 0).then(|| Duration::from_secs(secs as u64));
+
+		let builder = ReqwestClient::builder()
+			.connect_timeout(timeout(config.connect_timeout))
+			.timeout(timeout(config.request_timeout));
+
+		#[cfg(unix)]
+		if let Some(socket) = &self.socket {
+			return Ok(builder.unix_socket(socket.clone()));
+		}
+
+		match &self.fingerprint {
+			Some(fingerprint) if self.tls => Ok(builder.tls_backend_preconfigured(tls::pinned_config(fingerprint)?)),
+			_ => Ok(builder),
+		}
+	}
+
+	fn with_query(&self, mut request: RequestBuilder) -> Result<RequestBuilder> {
+		for (key, value) in &self.fields {
+			match value {
+				Field::Text(value) => request = request.query(&[(key, value)]),
+				Field::Texts(values) => {
+					for value in values {
+						request = request.query(&[(key, value)]);
+					}
+				}
+				Field::Binary(_) => bail!("Binary fields are not supported for GET requests"),
+			}
+		}
+
+		Ok(request)
+	}
+
 	fn format_url(&self, path: &str) -> String {
-		format!("http://{}:{}/{path}", self.address, self.port)
+		if self.socket.is_some() {
+			return format!("http://localhost/{path}");
+		}
+
+		format!("{}/{path}", self.url)
+	}
+
+	fn send(&self, request: RequestBuilder) -> Result<Response> {
+		let response = self.check(self.execute(request, true)?)?;
+
+		Ok(Response(response.status(), response.text().unwrap_or_default()))
+	}
+
+	/// Sends the request, retrying GET requests with backoff while the server is unreachable or unavailable
+	fn execute(&self, request: RequestBuilder, retry: bool) -> Result<ReqwestResponse> {
+		let (client, request) = request.header("User-Agent", USER_AGENT).build_split();
+		let mut request = request.desc("Failed to create request")?;
+
+		// Signatures cover the body, so streamed bodies like multipart forms are read into memory first
+		if let Some(body) = request.body_mut() {
+			body.buffer().desc("Failed to read request body")?;
+		}
+
+		let retries = match request.method() {
+			&Method::GET if retry => Config::new().request_retries,
+			_ => 0,
+		};
+
+		let mut attempt = 0;
+		let mut delay = RETRY_DELAY;
+
+		loop {
+			let next = if attempt < retries { request.try_clone() } else { None };
+			attempt += 1;
+
+			// Each attempt is signed separately as signatures cannot be reused
+			let result = self
+				.authorize(
+					request.method().as_str(),
+					request.url(),
+					request
+						.headers()
+						.get("Content-Type")
+						.and_then(|v| v.to_str().ok())
+						.unwrap_or_default(),
+					request.body().and_then(Body::as_bytes).unwrap_or_default(),
+				)
+				.and_then(|authorization| {
+					if let Some(authorization) = authorization {
+						request.headers_mut().insert(
+							"Authorization",
+							authorization.parse().desc("Failed to create authorization header")?,
+						);
+					}
+
+					client.execute(request).map_err(|err| self.classify(err).into())
+				});
+
+			let error = match result {
+				Ok(response)
+					if matches!(
+						response.status(),
+						StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+					) && next.is_some() =>
+				{
+					response.status().to_string()
+				}
+				Ok(response) => return Ok(response),
+				Err(err) if next.is_some() && err.downcast_ref::<Failure>().is_some_and(Failure::is_transient) => {
+					err.to_string()
+				}
+				Err(err) => return Err(err),
+			};
+
+			warn!(
+				"Request to {} failed: {error}, retrying in {}ms ({attempt}/{retries})",
+				self.url,
+				delay.as_millis()
+			);
+
+			thread::sleep(delay);
+			delay *= 2;
+			request = next.unwrap();
+		}
+	}
+
+	/// Turns responses showing that the credentials were rejected or the route is unknown to the server into failures
+	fn check(&self, response: ReqwestResponse) -> Result<ReqwestResponse> {
+		match response.status() {
+			StatusCode::UNAUTHORIZED => Err(Failure::Unauthorized.into()),
+			// Unknown routes have no body, unlike responses about missing programs
+			StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED if response.content_length() == Some(0) => {
+				Err(Failure::VersionMismatch(get_version(&response)).into())
+			}
+			_ => Ok(response),
+		}
+	}
+
+	/// Explains why the request could not be sent
+	fn classify(&self, err: reqwest::Error) -> Failure {
+		let timeout = |secs: usize| Duration::from_secs(secs as u64);
+
+		if err.is_timeout() {
+			return if err.is_connect() {
+				Failure::ConnectTimeout(self.url.clone(), timeout(Config::new().connect_timeout))
+			} else {
+				Failure::Timeout(timeout(Config::new().request_timeout))
+			};
+		}
+
+		if !err.is_connect() {
+			return Failure::Connect(err);
+		}
+
+		let mut source = error::Error::source(&err);
+
+		while let Some(err) = source {
+			match err.downcast_ref::<io::Error>().map(io::Error::kind) {
+				Some(ErrorKind::ConnectionRefused) => return Failure::Refused(self.url.clone()),
+				Some(ErrorKind::NetworkUnreachable | ErrorKind::HostUnreachable) => {
+					return Failure::Unreachable(self.url.clone());
+				}
+				_ => source = err.source(),
+			}
+		}
+
+		// Connection errors do not tell resolution failures apart, so resolve the address again to find out
+		if self.socket.is_none()
+			&& let Ok(url) = Url::parse(&self.url)
+			&& let Some(host) = url.host_str()
+			&& (host, url.port_or_known_default().unwrap_or_default())
+				.to_socket_addrs()
+				.is_err()
+		{
+			return Failure::Dns(host.to_owned());
+		}
+
+		Failure::Connect(err)
 	}
 
-	fn send(&self, mut request: RequestBuilder) -> Result<Response> {
-		if let Some(password) = &self.password {
-			request = request.header("Authorization", password);
+	/// Returns the `Authorization` header value for the given request
+	fn authorize(&self, method: &str, url: &Url, content_type: &str, body: &[u8]) -> Result<Option<String>> {
+		// Access to the Unix socket is already limited to the user running the server
+		let Some(password) = self.password.as_ref().filter(|_| self.socket.is_none()) else {
+			return Ok(None);
+		};
+
+		let credentials = match self.credentials.get() {
+			Some(credentials) => credentials,
+			None => {
+				let credentials = self.get_credentials(password)?;
+				self.credentials.get_or_init(|| credentials)
+			}
+		};
+
+		Ok(match credentials {
+			Credentials::Key { key, token } => {
+				let path = match url.query() {
+					Some(query) => format!("{}?{query}", url.path()),
+					None => url.path().to_owned(),
+				};
+
+				let request = SignedRequest {
+					method,
+					path: &path,
+					content_type,
+					body,
+				};
+
+				Some(Signature::new(key, token.as_deref(), &request).to_string())
+			}
+			Credentials::Legacy => Some(password.to_owned()),
+			Credentials::None => None,
+		})
+	}
+
+	fn get_credentials(&self, password: &str) -> Result<Credentials> {
+		// API tokens are used in place of the password
+		if let Some(id) = tokens::get_id(password) {
+			return Ok(Credentials::Key {
+				key: tokens::client_key(password),
+				token: Some(id.to_owned()),
+			});
 		}
 
-		let response = request
+		let response = self
+			.builder()?
+			.build()
+			.desc("Failed to create HTTP GET client")?
+			.get(self.format_url("auth"))
 			.header("User-Agent", USER_AGENT)
 			.send()
-			.desc("Failed to connect to the server")?;
+			.map_err(|err| self.classify(err))?;
 
-		Ok(Response(response.status(), response.text().unwrap_or_default()))
+		match response.status() {
+			// Servers that do not support signed requests yet
+			StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED if self.legacy_auth => {
+				racky_warn!(
+					"Server does not support signed requests, sending the plain password as legacy authentication is enabled"
+				);
+				Ok(Credentials::Legacy)
+			}
+			StatusCode::NOT_FOUND | StatusCode::UNAUTHORIZED => bail!(
+				"Server does not support signed requests, update it or allow sending the plain password with {}",
+				"racky server change --legacy-auth true".bold()
+			),
+			status if status.is_success() => {
+				let params = response.text().unwrap_or_default();
+
+				if params.is_empty() {
+					return Ok(Credentials::None);
+				}
+
+				let params = params
+					.parse::<HashParams>()
+					.desc("Failed to read server authentication parameters")?;
+
+				Ok(Credentials::Key {
+					key: params.client_key(password),
+					token: None,
+				})
+			}
+			status => bail!("Failed to get server authentication parameters ({status})"),
+		}
+	}
+}
+
+/// How the client authenticates to the server
+#[derive(Debug)]
+enum Credentials {
+	/// Requests are signed with the client key derived from the password or an API token with the given ID
+	Key { key: Vec<u8>, token: Option<String> },
+	/// The password or token is sent as is to servers that do not support signed requests
+	Legacy,
+	/// The server does not require a password
+	None,
+}
+
+/// Reason a request to the server failed, with a hint on how to fix it
+#[derive(Debug)]
+pub enum Failure {
+	/// The server address could not be resolved
+	Dns(String),
+	/// Nothing is listening on the server port
+	Refused(String),
+	/// There is no route to the server address
+	Unreachable(String),
+	/// The server did not accept the connection in time
+	ConnectTimeout(String, Duration),
+	/// The server accepted the connection but did not respond in time
+	Timeout(Duration),
+	/// The connection failed for another reason
+	Connect(reqwest::Error),
+	/// The server rejected the password or API token
+	Unauthorized,
+	/// The server does not know the request, with the version it reports if any
+	VersionMismatch(Option<String>),
+}
+
+impl Failure {
+	/// Returns true if the server may be reachable again after a while
+	pub fn is_transient(&self) -> bool {
+		matches!(
+			self,
+			Self::Refused(_) | Self::Unreachable(_) | Self::ConnectTimeout(..) | Self::Timeout(_) | Self::Connect(_)
+		)
+	}
+}
+
+impl Display for Failure {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let version = env!("CARGO_PKG_VERSION");
+
+		match self {
+			Self::Dns(host) => write!(f, "Could not resolve server address {host}, check that it is correct"),
+			Self::Refused(url) => write!(
+				f,
+				"Connection to {url} was refused, check that the server is running and the port is correct"
+			),
+			Self::Unreachable(url) => write!(
+				f,
+				"Server at {url} is unreachable, check the address and the network connection"
+			),
+			Self::ConnectTimeout(url, timeout) => write!(
+				f,
+				"Timed out after {}s connecting to {url}, the server may be down or behind a firewall",
+				timeout.as_secs()
+			),
+			Self::Timeout(timeout) => write!(
+				f,
+				"Server did not respond within {}s, increase `request_timeout` if the request takes longer",
+				timeout.as_secs()
+			),
+			Self::Connect(err) => write!(f, "Failed to connect to the server: {err}"),
+			Self::Unauthorized => write!(
+				f,
+				"Server rejected the credentials, check the password or API token saved for it"
+			),
+			Self::VersionMismatch(Some(server)) if server != version => write!(
+				f,
+				"Server does not support this request, it runs Racky {server} while the client runs {version}"
+			),
+			Self::VersionMismatch(Some(_)) => write!(f, "Server does not support this request"),
+			Self::VersionMismatch(None) => write!(
+				f,
+				"Server does not support this request, it runs an older version of Racky than the client ({version})"
+			),
+		}
+	}
+}
+
+impl error::Error for Failure {}
+
+fn get_version(response: &ReqwestResponse) -> Option<String> {
+	response
+		.headers()
+		.get(VERSION_HEADER)
+		.and_then(|version| version.to_str().ok())
+		.map(str::to_owned)
+}
+
+/// Returns the Unix socket of the server running on this machine if the server is the one it saved as local
+/// and the socket accepts connections, other servers on this machine are reached over the network with their password
+#[cfg(unix)]
+fn get_socket(alias: &str, server: &Server) -> Option<PathBuf> {
+	let path = dirs::socket();
+	(alias == servers::LOCAL && server.is_local() && UnixStream::connect(&path).is_ok()).then_some(path)
+}
+
+#[cfg(not(unix))]
+fn get_socket(_alias: &str, _server: &Server) -> Option<PathBuf> {
+	None
+}
+
+/// Sends the request built by `send` to every server matching the selector in parallel, see `servers::resolve`.
+/// A single server prints its response after the prefix, several servers print a table with a row for each of them
+/// and fail if any of them failed
+pub fn run<F>(selector: Option<String>, prefix: &str, send: F) -> Result<()>
+where
+	F: Fn(Client) -> Result<Response> + Sync,
+{
+	let servers = servers::resolve(selector)?;
+
+	if let [(alias, server)] = servers.as_slice() {
+		return send(Client::new(alias, server))?.with_prefix(prefix).handle();
+	}
+
+	let results = thread::scope(|scope| {
+		servers
+			.iter()
+			.map(|(alias, server)| (alias, scope.spawn(|| send(Client::new(alias, server)))))
+			.collect::<Vec<_>>()
+			.into_iter()
+			.map(|(alias, handle)| {
+				let result = handle.join().unwrap_or_else(|_| Err(anyhow!("Request panicked")));
+				(alias, result)
+			})
+			.collect::<Vec<_>>()
+	});
+
+	let mut table = Table::new();
+	table.set_header(vec!["Server", "Status", "Response"]);
+
+	let mut failed = 0;
+
+	for (alias, result) in results {
+		let (status, response) = match result {
+			Ok(Response(status, response)) if status.is_success() => (status.to_string(), response),
+			Ok(Response(status, response)) => {
+				failed += 1;
+				(status.to_string(), response)
+			}
+			Err(err) => {
+				failed += 1;
+				(String::from("Error"), format!("{err:#}"))
+			}
+		};
+
+		table.add_row(vec![alias.to_owned(), status, response.trim_end().to_owned()]);
 	}
+
+	racky_info!("Results from {} servers:\n{table}", servers.len());
+
+	if failed > 0 {
+		bail!("Request failed on {failed} of {} servers", servers.len());
+	}
+
+	Ok(())
+}
+
+/// Streams events opened by `open` from every server matching the selector in parallel until they end,
+/// lines from several servers are prefixed with the server alias
+pub fn follow<O, F>(selector: Option<String>, header: &str, open: O, format: F) -> Result<()>
+where
+	O: Fn(Client) -> Result<Events> + Sync,
+	F: Fn(String) -> Result<String> + Sync,
+{
+	let servers = servers::resolve(selector)?;
+
+	if let [(alias, server)] = servers.as_slice() {
+		let events = open(Client::new(alias, server))?;
+
+		racky_info!("{header}");
+
+		for data in events {
+			println!("{}", format(data?)?);
+		}
+
+		return Ok(());
+	}
+
+	racky_info!("{header}");
+
+	let (open, format) = (&open, &format);
+
+	let failed = thread::scope(|scope| {
+		let handles = servers
+			.iter()
+			.map(|(alias, server)| {
+				scope.spawn(move || -> Result<()> {
+					for data in open(Client::new(alias, server))? {
+						println!("{} {}", format!("[{alias}]").bold(), format(data?)?);
+					}
+
+					Ok(())
+				})
+			})
+			.collect::<Vec<_>>();
+
+		servers
+			.iter()
+			.zip(handles)
+			.filter_map(|((alias, _), handle)| match handle.join() {
+				Ok(Ok(())) => None,
+				Ok(Err(err)) => Some(format!("{}: {err:#}", alias.bold())),
+				Err(_) => Some(format!("{}: Stream panicked", alias.bold())),
+			})
+			.collect::<Vec<_>>()
+	});
+
+	if !failed.is_empty() {
+		bail!(
+			"Streaming failed on {} of {} servers:\n{}",
+			failed.len(),
+			servers.len(),
+			failed.join("\n")
+		);
+	}
+
+	Ok(())
 }
 
 #[derive(Debug)]
@@ -136,20 +714,61 @@ impl Response {
 	}
 
 	pub fn handle(self) -> Result<()> {
-		if self.0.is_success() {
-			racky_info!("{}", self.1);
-		} else if !self.1.is_empty() {
-			bail!("{} ({})", self.1, self.0);
-		} else {
-			bail!("{}", self.0);
+		if !self.0.is_success() {
+			return Err(self.into_error());
 		}
 
+		racky_info!("{}", self.1);
+
 		Ok(())
 	}
+
+	fn into_error(self) -> Error {
+		if self.1.is_empty() {
+			anyhow!("{}", self.0)
+		} else {
+			anyhow!("{} ({})", self.1, self.0)
+		}
+	}
+}
+
+/// Data of server-sent events, comments and other fields are skipped
+#[derive(Debug)]
+pub struct Events(BufReader<ReqwestResponse>);
+
+impl Iterator for Events {
+	type Item = Result<String>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let mut data = Vec::new();
+		let mut line = String::new();
+
+		loop {
+			line.clear();
+
+			match self.0.read_line(&mut line) {
+				Ok(0) => return None,
+				Ok(_) => {}
+				Err(err) => return Some(Err(err).desc("Failed to read from the server")),
+			}
+
+			let line = line.trim_end_matches(['\n', '\r']);
+
+			if line.is_empty() {
+				if !data.is_empty() {
+					return Some(Ok(data.join("\n")));
+				}
+			} else if let Some(value) = line.strip_prefix("data:") {
+				data.push(value.strip_prefix(' ').unwrap_or(value).to_owned());
+			}
+		}
+	}
 }
 
 #[derive(Debug)]
 enum Field {
 	Text(String),
+	/// Text field repeated for each of the values
+	Texts(Vec<String>),
 	Binary(Vec<u8>),
 }
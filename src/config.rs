@@ -13,12 +13,15 @@ use optfield::optfield;
 use serde::{Deserialize, Serialize};
 use toml;
 
-use crate::{dirs, ext::ResultExt, logger::Table};
+use crate::{auth::PasswordHash, dirs, ext::ResultExt, logger::Table};
 
 lazy_static! {
 	static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
 }
 
+/// Settings that are only stored as salted hashes of their values
+pub const HASHED: [&str; 2] = ["password", "metrics_token"];
+
 #[optfield(OptConfig, merge_fn, attrs = (derive(Deserialize)))]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, DocumentedFields, Val, Iter, Get, Set)]
 pub struct Config {
@@ -26,12 +29,42 @@ pub struct Config {
 	pub address: String,
 	/// Default server port
 	pub port: u16,
-	/// Default server password
+	/// Default server password, stored as a salted hash
 	pub password: String,
+	/// Whether to accept the plain password in the `Authorization` header from clients that do not sign requests
+	pub legacy_auth: bool,
+	/// Delay in milliseconds added to the response for each failed authentication attempt from the same address
+	pub auth_delay: usize,
+	/// Failed authentication attempts from the same address before it is locked out (0 disables the lockout)
+	pub auth_max_failures: usize,
+	/// How long in seconds an address is locked out for, also how long failed attempts are remembered
+	pub auth_lockout: usize,
+	/// Whether to also listen on `~/.racky/socket/racky.sock`, used without the password for the `local` server (Unix only)
+	pub socket: bool,
+	/// Whether to serve HTTPS instead of plain HTTP
+	pub tls: bool,
+	/// Path to the PEM certificate chain used for HTTPS (empty uses `~/.racky/tls/cert.pem`)
+	pub tls_cert: String,
+	/// Path to the PEM private key used for HTTPS (empty uses `~/.racky/tls/key.pem`)
+	pub tls_key: String,
+	/// Whether to generate a self-signed certificate when HTTPS is enabled and no certificate exists
+	pub tls_generate: bool,
+	/// Whether programs keep running when the server service stops, so the next server adopts them instead of
+	/// starting them again (sets `KillMode=process`, applied by `racky install --server`)
+	pub keep_programs: bool,
+	/// Token the `/metrics` endpoint accepts as a bearer token besides the usual authentication, stored as a salted hash
+	/// (empty to disable)
+	pub metrics_token: String,
 	/// Maximum size of a log file in megabytes
 	pub log_size_limit: usize,
 	/// Maximum number of log files to keep
 	pub log_file_limit: usize,
+	/// Time in seconds the client waits for a connection to a server before giving up (0 disables it)
+	pub connect_timeout: usize,
+	/// Time in seconds a client request may take once connected, including uploads and the response (0 disables it)
+	pub request_timeout: usize,
+	/// How many times the client retries read-only requests when a server is unreachable or unavailable
+	pub request_retries: usize,
 }
 
 impl Default for Config {
@@ -40,8 +73,22 @@ impl Default for Config {
 			address: String::from("0.0.0.0"),
 			port: 5000,
 			password: String::new(),
+			legacy_auth: false,
+			auth_delay: 500,
+			auth_max_failures: 10,
+			auth_lockout: 900,
+			socket: true,
+			tls: false,
+			tls_cert: String::new(),
+			tls_key: String::new(),
+			tls_generate: true,
+			keep_programs: false,
+			metrics_token: String::new(),
 			log_size_limit: 10,
 			log_file_limit: 20,
+			connect_timeout: 5,
+			request_timeout: 60,
+			request_retries: 2,
 		}
 	}
 }
@@ -73,6 +120,20 @@ impl Config {
 			Err(err) => error!("Racky config could not be loaded: {err}"),
 		}
 
+		// Configs written by older versions or edited by hand store the secrets in plain text
+		let mut migrated = false;
+
+		for secret in [&mut config.password, &mut config.metrics_token] {
+			if !secret.is_empty() && !PasswordHash::is_hash(secret) {
+				*secret = PasswordHash::new(secret).to_string();
+				migrated = true;
+			}
+		}
+
+		if migrated && config.save().is_ok() {
+			info!("Racky config secrets migrated to salted verifiers");
+		}
+
 		*CONFIG.write().unwrap() = config;
 	}
 
@@ -90,6 +151,14 @@ impl Config {
 	}
 
 	pub fn update(&mut self, key: &str, value: &str) -> Result<()> {
+		let hash;
+		let value = if HASHED.contains(&key) && !value.is_empty() && !PasswordHash::is_hash(value) {
+			hash = PasswordHash::new(value).to_string();
+			&hash
+		} else {
+			value
+		};
+
 		let result = self
 			.set(key, value)
 			.with_context(|| format!("Failed to set `{key}` to `{value}`"));
@@ -1,6 +1,9 @@
 use axum::extract::DefaultBodyLimit;
 
 pub const USER_AGENT: &str = "Racky CLI";
-pub const BODY_SIZE_LIMIT: DefaultBodyLimit = DefaultBodyLimit::max(100 * 1024 * 1024);
+/// Response header with the version of the server, used by clients to explain unsupported requests
+pub const VERSION_HEADER: &str = "Racky-Version";
+pub const MAX_BODY_SIZE: usize = 100 * 1024 * 1024;
+pub const BODY_SIZE_LIMIT: DefaultBodyLimit = DefaultBodyLimit::max(MAX_BODY_SIZE);
 
 pub const GIGABYTE: f64 = 1024.0 * 1024.0 * 1024.0;
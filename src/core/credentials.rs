@@ -0,0 +1,121 @@
+use std::process::Command as StdCommand;
+#[cfg(target_os = "linux")]
+use std::{ffi::CString, io, os::unix::process::CommandExt, path::PathBuf};
+
+use anyhow::Result;
+#[cfg(not(target_os = "linux"))]
+use anyhow::bail;
+#[cfg(target_os = "linux")]
+use anyhow::{Context, ensure};
+#[cfg(target_os = "linux")]
+use nix::unistd::{self, Gid, Group, Uid, User};
+
+#[cfg(target_os = "linux")]
+use crate::ext::ResultExt;
+
+/// User and groups a program runs as
+#[cfg(target_os = "linux")]
+#[derive(Debug)]
+pub struct Credentials {
+	name: String,
+	home: PathBuf,
+	uid: Uid,
+	gid: Gid,
+	groups: Vec<Gid>,
+}
+
+#[cfg(target_os = "linux")]
+impl Credentials {
+	/// Looks up the given user and group, returns `None` if the program should run as the server user
+	pub fn resolve(user: &str, group: &str) -> Result<Option<Self>> {
+		if user.is_empty() && group.is_empty() {
+			return Ok(None);
+		}
+
+		let current = Uid::effective();
+		let user = if user.is_empty() {
+			User::from_uid(current)
+		} else if let Ok(uid) = user.parse::<u32>() {
+			User::from_uid(Uid::from_raw(uid))
+		} else {
+			User::from_name(user)
+		}
+		.desc("Failed to look up user")?
+		.with_context(|| format!("User `{user}` does not exist"))?;
+
+		let gid = if group.is_empty() {
+			user.gid
+		} else if let Ok(gid) = group.parse::<u32>() {
+			Gid::from_raw(gid)
+		} else {
+			Group::from_name(group)
+				.desc("Failed to look up group")?
+				.with_context(|| format!("Group `{group}` does not exist"))?
+				.gid
+		};
+
+		if user.uid == current && gid == Gid::effective() {
+			return Ok(None);
+		}
+
+		ensure!(
+			current.is_root(),
+			"Racky must run as root to run programs as user `{}` and group `{gid}`",
+			user.name
+		);
+
+		let mut groups = unistd::getgrouplist(&CString::new(user.name.as_str())?, user.gid)
+			.desc("Failed to get supplementary groups")?;
+
+		if !groups.contains(&gid) {
+			groups.push(gid);
+		}
+
+		Ok(Some(Self {
+			name: user.name,
+			home: user.dir,
+			uid: user.uid,
+			gid,
+			groups,
+		}))
+	}
+
+	/// Sets the environment of the user and switches to it before the process executes
+	pub fn apply(&self, command: &mut StdCommand) {
+		let (uid, gid, groups) = (self.uid, self.gid, self.groups.clone());
+
+		command
+			.env("HOME", &self.home)
+			.env("USER", &self.name)
+			.env("LOGNAME", &self.name);
+
+		// Switching happens after changing the working directory, so it only has to be accessible to the server
+		unsafe {
+			command.pre_exec(move || {
+				unistd::setgroups(&groups).map_err(io::Error::from)?;
+				unistd::setgid(gid).map_err(io::Error::from)?;
+				unistd::setuid(uid).map_err(io::Error::from)?;
+
+				Ok(())
+			});
+		}
+	}
+}
+
+/// User and groups a program runs as (only supported on Linux)
+#[cfg(not(target_os = "linux"))]
+#[derive(Debug)]
+pub struct Credentials;
+
+#[cfg(not(target_os = "linux"))]
+impl Credentials {
+	pub fn resolve(user: &str, group: &str) -> Result<Option<Self>> {
+		if !user.is_empty() || !group.is_empty() {
+			bail!("Running programs as a different user is only supported on Linux");
+		}
+
+		Ok(None)
+	}
+
+	pub fn apply(&self, _command: &mut StdCommand) {}
+}
@@ -0,0 +1,63 @@
+use std::fmt::{self, Display, Formatter};
+
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::Sender;
+
+use crate::core::program::Status;
+
+/// Change of a program status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+	pub program: String,
+	pub old_status: Status,
+	pub new_status: Status,
+	/// Exit code of the process when the new status is `Finished` or `Errored`
+	pub exit_code: Option<i32>,
+	pub timestamp: Timestamp,
+}
+
+impl Display for Event {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"[{:.0}] {}: {} -> {}",
+			self.timestamp, self.program, self.old_status, self.new_status
+		)?;
+
+		if let Some(code) = self.exit_code {
+			write!(f, " (exit code {code})")?;
+		}
+
+		Ok(())
+	}
+}
+
+/// Publishes status changes of a single program to the core event channel
+#[derive(Debug, Clone)]
+pub struct Publisher {
+	program: String,
+	sender: Sender<Event>,
+}
+
+impl Publisher {
+	pub fn new(program: &str, sender: Sender<Event>) -> Self {
+		Self {
+			program: program.to_owned(),
+			sender,
+		}
+	}
+
+	pub fn publish(&self, old_status: &Status, new_status: &Status, exit_code: Option<i32>) {
+		// Sending only fails when there are no subscribers
+		self.sender
+			.send(Event {
+				program: self.program.clone(),
+				old_status: old_status.clone(),
+				new_status: new_status.clone(),
+				exit_code,
+				timestamp: Timestamp::now(),
+			})
+			.ok();
+	}
+}
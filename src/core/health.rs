@@ -0,0 +1,307 @@
+use std::{
+	collections::HashSet,
+	fmt::{self, Display, Formatter},
+	net::{TcpStream, ToSocketAddrs},
+	path::Path,
+	process::{Command as StdCommand, Stdio},
+	str::FromStr,
+	sync::{Arc, Mutex},
+	thread,
+	time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, Result, bail};
+use colored::Colorize;
+use command_group::CommandGroup;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+	consts::USER_AGENT,
+	core::{
+		CorePtr,
+		credentials::Credentials,
+		program::{List, ProgramPtr, Status},
+	},
+	ext::ResultExt,
+	racky_error, racky_info, racky_warn,
+};
+
+/// Health probe of a program, parsed from `http://<url>`, `tcp://<host>:<port>` or `exec:<command>`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Probe {
+	#[default]
+	None,
+	Http(String),
+	Tcp(String),
+	Exec(List),
+}
+
+impl Probe {
+	pub fn is_none(&self) -> bool {
+		matches!(self, Probe::None)
+	}
+
+	/// Runs the probe, exec probes run with the given credentials like the program itself
+	pub fn check(&self, timeout: Duration, working_dir: &Path, credentials: Option<&Credentials>) -> Result<()> {
+		match self {
+			Probe::None => Ok(()),
+			Probe::Http(url) => {
+				let status = Client::builder()
+					.timeout(timeout)
+					.build()
+					.desc("Failed to create HTTP client")?
+					.get(url)
+					.header("User-Agent", USER_AGENT)
+					.send()
+					.desc("HTTP request failed")?
+					.status();
+
+				if !status.is_success() {
+					bail!("HTTP request returned {status}");
+				}
+
+				Ok(())
+			}
+			Probe::Tcp(address) => {
+				let address = address
+					.to_socket_addrs()
+					.desc("Failed to resolve address")?
+					.next()
+					.context("Address did not resolve to any socket")?;
+
+				TcpStream::connect_timeout(&address, timeout)
+					.map(drop)
+					.desc("TCP connection failed")
+			}
+			Probe::Exec(command) => {
+				let (program, args) = command.split_first().context("Command is empty")?;
+				let mut command = StdCommand::new(program);
+
+				if let Some(credentials) = credentials {
+					credentials.apply(&mut command);
+				}
+
+				// The command runs in its own process group, so a timeout also kills the processes it started
+				let mut child = command
+					.args(args)
+					.current_dir(working_dir)
+					.stdin(Stdio::null())
+					.stdout(Stdio::null())
+					.stderr(Stdio::null())
+					.group_spawn()
+					.desc("Failed to run command")?;
+
+				let deadline = Instant::now() + timeout;
+
+				loop {
+					if let Some(status) = child.try_wait()? {
+						if !status.success() {
+							bail!("Command exited with {status}");
+						}
+
+						return Ok(());
+					}
+
+					if Instant::now() >= deadline {
+						child.kill().ok();
+						child.wait().ok();
+						bail!("Command timed out");
+					}
+
+					thread::sleep(Duration::from_millis(50));
+				}
+			}
+		}
+	}
+}
+
+impl FromStr for Probe {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+
+		if s.is_empty() {
+			Ok(Probe::None)
+		} else if s.starts_with("http://") || s.starts_with("https://") {
+			Ok(Probe::Http(s.to_owned()))
+		} else if let Some(address) = s.strip_prefix("tcp://") {
+			Ok(Probe::Tcp(address.to_owned()))
+		} else if let Some(command) = s.strip_prefix("exec:") {
+			let command = command.parse::<List>()?;
+
+			if command.is_empty() {
+				bail!("Health check command is empty");
+			}
+
+			Ok(Probe::Exec(command))
+		} else {
+			bail!("Health check must start with `http://`, `https://`, `tcp://` or `exec:`")
+		}
+	}
+}
+
+impl TryFrom<String> for Probe {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl From<Probe> for String {
+	fn from(value: Probe) -> Self {
+		value.to_string()
+	}
+}
+
+impl Display for Probe {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Probe::None => Ok(()),
+			Probe::Http(url) => write!(f, "{url}"),
+			Probe::Tcp(address) => write!(f, "tcp://{address}"),
+			Probe::Exec(command) => write!(f, "exec:{command}"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Health {
+	pub failures: usize,
+	pub checked: Option<SystemTime>,
+	pub error: Option<String>,
+}
+
+impl Health {
+	pub fn is_healthy(&self) -> bool {
+		self.checked.is_some() && self.failures == 0
+	}
+}
+
+impl Display for Health {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match (&self.checked, &self.error) {
+			(None, _) => write!(f, "Unknown"),
+			(Some(_), None) => write!(f, "Healthy"),
+			(Some(_), Some(error)) => write!(f, "{} failed check(s): {error}", self.failures),
+		}
+	}
+}
+
+/// Periodically probes the program until it exits or gets restarted
+pub fn monitor(program: ProgramPtr, index: usize) {
+	thread::spawn(move || {
+		let name = program.name().bold();
+
+		loop {
+			let config = program.config();
+
+			if config.health_check.is_none() {
+				break;
+			}
+
+			thread::sleep(Duration::from_secs(config.health_interval.max(1) as u64));
+
+			if program.state().index() != index || !program.status().is_alive() {
+				break;
+			}
+
+			let result = Credentials::resolve(&config.user, &config.group).and_then(|credentials| {
+				config.health_check.check(
+					Duration::from_secs(config.health_timeout.max(1) as u64),
+					&program.paths().get_working_directory(),
+					credentials.as_ref(),
+				)
+			});
+
+			let mut state = program.state_mut();
+
+			if state.index() != index {
+				break;
+			}
+
+			state.health.checked = Some(SystemTime::now());
+
+			let pid = match state.status {
+				Status::Running(pid) | Status::Unhealthy(pid) => pid,
+				_ => break,
+			};
+
+			match result {
+				Ok(()) => {
+					if state.status == Status::Unhealthy(pid) {
+						racky_info!("Program {name} is healthy again");
+						state.set_status(Status::Running(pid));
+					}
+
+					state.health.failures = 0;
+					state.health.error = None;
+				}
+				Err(err) => {
+					state.health.failures += 1;
+					state.health.error = Some(err.to_string());
+
+					if state.health.failures < config.health_threshold.max(1) || state.status == Status::Unhealthy(pid)
+					{
+						continue;
+					}
+
+					racky_warn!(
+						"Program {name} is unhealthy after {} failed health checks: {err}",
+						state.health.failures.to_string().bold()
+					);
+					state.set_status(Status::Unhealthy(pid));
+				}
+			}
+		}
+	});
+}
+
+/// Restarts programs with `health_restart` enabled through the core, like a manual restart, when they become unhealthy
+pub fn restart_unhealthy(core: CorePtr) {
+	let mut events = core.subscribe();
+	let restarting = Arc::new(Mutex::new(HashSet::new()));
+
+	thread::spawn(move || {
+		loop {
+			let programs = match events.blocking_recv() {
+				Ok(event) if matches!(event.new_status, Status::Unhealthy(_)) => {
+					core.get_program(&event.program).into_iter().collect()
+				}
+				Ok(_) => continue,
+				// Events were missed, so programs that became unhealthy in the meantime are looked up
+				Err(RecvError::Lagged(_)) => core.programs().values().cloned().collect::<Vec<_>>(),
+				Err(RecvError::Closed) => break,
+			};
+
+			for program in programs {
+				if !matches!(program.status(), Status::Unhealthy(_)) || !program.config().health_restart {
+					continue;
+				}
+
+				if !restarting.lock().unwrap().insert(program.name().to_string()) {
+					continue;
+				}
+
+				racky_info!("Program {} is unhealthy and will be restarted", program.name().bold());
+
+				let (core, restarting) = (core.clone(), restarting.clone());
+
+				thread::spawn(move || {
+					if let Err(err) = core.restart_program(&program) {
+						racky_error!(
+							"Program {} could not be restarted after failing health checks: {err}",
+							program.name().bold()
+						);
+					}
+
+					restarting.lock().unwrap().remove(program.name());
+				});
+			}
+		}
+	});
+}
@@ -0,0 +1,366 @@
+#[cfg(unix)]
+use std::{ffi::CString, io, os::unix::process::CommandExt};
+use std::{
+	fmt::{self, Display, Formatter},
+	fs,
+	path::{Path, PathBuf},
+	process::Command as StdCommand,
+	str::FromStr,
+	sync::OnceLock,
+};
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+use log::{debug, trace};
+#[cfg(unix)]
+use nix::{
+	fcntl::{self, OFlag},
+	sys::{
+		resource::{self, Resource},
+		stat::Mode,
+	},
+	unistd,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{core::program::Config, ext::ResultExt, racky_warn, util};
+
+const CGROUP_MOUNT: &str = "/sys/fs/cgroup";
+const CONTROLLERS: [&str; 3] = ["memory", "cpu", "pids"];
+const CPU_PERIOD: u64 = 100_000;
+
+static CGROUP_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// Amount of memory in bytes, parsed from values like `512M`, `1.5G` or `1024` (empty or 0 for no limit)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Size(u64);
+
+impl Size {
+	pub fn is_none(&self) -> bool {
+		self.0 == 0
+	}
+
+	pub fn bytes(&self) -> u64 {
+		self.0
+	}
+}
+
+impl FromStr for Size {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim().to_uppercase();
+		let s = s.trim_end_matches("IB").trim_end_matches('B');
+
+		if s.is_empty() {
+			return Ok(Self(0));
+		}
+
+		let (number, multiplier) = match s.chars().last() {
+			Some('K') => (&s[..s.len() - 1], 1 << 10),
+			Some('M') => (&s[..s.len() - 1], 1 << 20),
+			Some('G') => (&s[..s.len() - 1], 1 << 30),
+			Some('T') => (&s[..s.len() - 1], 1u64 << 40),
+			_ => (s, 1),
+		};
+
+		let number = number
+			.trim()
+			.parse::<f64>()
+			.ok()
+			.filter(|number| number.is_finite() && *number >= 0.0);
+
+		match number {
+			Some(number) => Ok(Self((number * multiplier as f64) as u64)),
+			None => bail!("Expected a size like `512M` or `2G`, got `{s}`"),
+		}
+	}
+}
+
+impl TryFrom<String> for Size {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl From<Size> for String {
+	fn from(value: Size) -> Self {
+		value.to_string()
+	}
+}
+
+impl Display for Size {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		if self.0 == 0 {
+			return Ok(());
+		}
+
+		for (unit, multiplier) in [("T", 1u64 << 40), ("G", 1 << 30), ("M", 1 << 20), ("K", 1 << 10)] {
+			if self.0.is_multiple_of(multiplier) {
+				return write!(f, "{}{unit}", self.0 / multiplier);
+			}
+		}
+
+		write!(f, "{}", self.0)
+	}
+}
+
+/// Resource limits prepared for a single program run
+#[derive(Debug, Default)]
+pub struct Limits {
+	cgroup: Option<PathBuf>,
+	events: Events,
+	open_files: Option<u64>,
+	memory: Size,
+	processes: usize,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Events {
+	oom_kills: u64,
+	pids_max: u64,
+}
+
+impl Limits {
+	/// Prepares the limits of a program, creating its cgroup if cgroups v2 are available
+	pub fn new(name: &str, config: &Config) -> Self {
+		let mut limits = Self {
+			memory: config.memory_limit,
+			processes: config.processes_limit,
+			..Default::default()
+		};
+
+		if config.open_files_limit > 0 {
+			limits.open_files = Some(config.open_files_limit as u64);
+		}
+
+		let uses_cgroup = !config.memory_limit.is_none() || config.cpu_limit > 0.0 || config.processes_limit > 0;
+
+		if !uses_cgroup {
+			return limits;
+		}
+
+		if let Some(root) = cgroup_root() {
+			let path = root.join(name);
+
+			match Self::configure_cgroup(&path, config) {
+				Ok(()) => {
+					trace!("Cgroup of program {name} configured at {path:?}");
+
+					limits.events = Events::read(&path);
+					limits.cgroup = Some(path);
+
+					return limits;
+				}
+				Err(err) => racky_warn!("Failed to configure cgroup of program {}: {err}", name.bold()),
+			}
+		}
+
+		// There are no rlimit equivalents: RLIMIT_AS limits virtual rather than used memory
+		// and RLIMIT_NPROC counts all processes of the user, not just those of the program
+		for (limit, enabled) in [
+			("memory", !config.memory_limit.is_none()),
+			("CPU", config.cpu_limit > 0.0),
+			("process", config.processes_limit > 0),
+		] {
+			if enabled {
+				racky_warn!(
+					"Program {} has a {limit} limit but cgroups v2 are not available, the limit will be ignored",
+					name.bold()
+				);
+			}
+		}
+
+		limits
+	}
+
+	/// Makes the spawned process join the program cgroup and applies rlimits before it executes
+	#[cfg(unix)]
+	pub fn apply(&self, command: &mut StdCommand) -> Result<()> {
+		let procs = match &self.cgroup {
+			Some(path) => Some(CString::new(path.join("cgroup.procs").to_string_lossy().as_bytes())?),
+			None => None,
+		};
+		let open_files = self.open_files;
+
+		if procs.is_none() && open_files.is_none() {
+			return Ok(());
+		}
+
+		// Only async-signal-safe calls are allowed between fork and exec
+		unsafe {
+			command.pre_exec(move || {
+				if let Some(procs) = &procs {
+					let fd = fcntl::open(procs.as_c_str(), OFlag::O_WRONLY, Mode::empty())?;
+					let result = unistd::write(fd, b"0");
+
+					unistd::close(fd).ok();
+					result?;
+				}
+
+				if let Some(limit) = open_files {
+					resource::setrlimit(Resource::RLIMIT_NOFILE, limit, limit).map_err(io::Error::from)?;
+				}
+
+				Ok(())
+			});
+		}
+
+		Ok(())
+	}
+
+	#[cfg(windows)]
+	pub fn apply(&self, _command: &mut StdCommand) -> Result<()> {
+		if self.cgroup.is_some() || self.open_files.is_some() {
+			bail!("Resource limits are not supported on Windows");
+		}
+
+		Ok(())
+	}
+
+	/// Returns the description of a limit the program exceeded during its last run
+	pub fn get_violation(&self) -> Option<String> {
+		let path = self.cgroup.as_ref()?;
+		let events = Events::read(path);
+
+		if events.oom_kills > self.events.oom_kills {
+			Some(format!("Out of memory: exceeded memory limit of {}", self.memory))
+		} else if events.pids_max > self.events.pids_max {
+			Some(format!("Exceeded process limit of {}", self.processes))
+		} else {
+			None
+		}
+	}
+
+	fn configure_cgroup(path: &Path, config: &Config) -> Result<()> {
+		if !path.exists() {
+			fs::create_dir(path).desc("Failed to create cgroup")?;
+		}
+
+		let memory = if config.memory_limit.is_none() {
+			String::from("max")
+		} else {
+			config.memory_limit.bytes().to_string()
+		};
+
+		let cpu = if config.cpu_limit > 0.0 {
+			format!(
+				"{} {CPU_PERIOD}",
+				(config.cpu_limit * CPU_PERIOD as f64).max(1000.0) as u64
+			)
+		} else {
+			format!("max {CPU_PERIOD}")
+		};
+
+		let pids = if config.processes_limit > 0 {
+			config.processes_limit.to_string()
+		} else {
+			String::from("max")
+		};
+
+		fs::write(path.join("memory.max"), memory).desc("Failed to set memory limit")?;
+		fs::write(path.join("cpu.max"), cpu).desc("Failed to set CPU limit")?;
+		fs::write(path.join("pids.max"), pids).desc("Failed to set process limit")?;
+
+		// Without this the memory limit could be bypassed by swapping
+		if path.join("memory.swap.max").exists() {
+			fs::write(path.join("memory.swap.max"), "0").desc("Failed to set swap limit")?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Events {
+	fn read(path: &Path) -> Self {
+		let read = |file: &str, key: &str| {
+			fs::read_to_string(path.join(file))
+				.ok()
+				.and_then(|contents| {
+					contents.lines().find_map(|line| {
+						line.strip_prefix(key)
+							.and_then(|value| value.trim().parse::<u64>().ok())
+					})
+				})
+				.unwrap_or_default()
+		};
+
+		Self {
+			oom_kills: read("memory.events", "oom_kill "),
+			pids_max: read("pids.events", "max "),
+		}
+	}
+}
+
+/// Prepares a delegated cgroup v2 sub-tree for programs, if the server runs as a systemd service
+///
+/// The service cgroup ends up with two children: `server`, which the server process moves itself into,
+/// and `programs`, which holds one cgroup per limited program. Tools that inspect the service cgroup,
+/// like `systemctl status`, show the server in the `server` leaf as a result.
+pub fn init() {
+	let root = CGROUP_ROOT.get_or_init(|| match create_cgroup_root() {
+		Ok(root) => Some(root),
+		Err(err) => {
+			debug!("Cgroups v2 are not available, falling back to rlimits: {err}");
+			None
+		}
+	});
+
+	if let Some(root) = root {
+		trace!("Program cgroups will be created in {root:?}");
+	}
+}
+
+fn cgroup_root() -> Option<PathBuf> {
+	CGROUP_ROOT.get().cloned().flatten()
+}
+
+fn create_cgroup_root() -> Result<PathBuf> {
+	if !cfg!(target_os = "linux") || !util::is_service() {
+		bail!("Server is not running as a systemd service");
+	}
+
+	let mount = PathBuf::from(CGROUP_MOUNT);
+
+	if !mount.join("cgroup.controllers").exists() {
+		bail!("Unified cgroup hierarchy is not mounted");
+	}
+
+	let own = fs::read_to_string("/proc/self/cgroup").desc("Failed to read own cgroup")?;
+	let own = match own.lines().find_map(|line| line.strip_prefix("0::")) {
+		Some(own) => mount.join(own.trim_start_matches('/')),
+		None => bail!("Server is not in a cgroup v2 hierarchy"),
+	};
+
+	let available = fs::read_to_string(own.join("cgroup.controllers")).desc("Failed to read cgroup controllers")?;
+	let controllers = CONTROLLERS
+		.iter()
+		.filter(|controller| available.split_whitespace().any(|c| c == **controller))
+		.map(|controller| format!("+{controller}"))
+		.collect::<Vec<_>>()
+		.join(" ");
+
+	if controllers.is_empty() {
+		bail!("None of the required cgroup controllers are delegated");
+	}
+
+	// Processes cannot live in a cgroup that distributes resources to its children,
+	// so the server moves itself into a leaf cgroup first
+	let server = own.join("server");
+	let programs = own.join("programs");
+
+	for path in [&server, &programs] {
+		if !path.exists() {
+			fs::create_dir(path).desc("Failed to create cgroup")?;
+		}
+	}
+
+	fs::write(server.join("cgroup.procs"), std::process::id().to_string()).desc("Failed to move server cgroup")?;
+	fs::write(own.join("cgroup.subtree_control"), &controllers).desc("Failed to enable cgroup controllers")?;
+	fs::write(programs.join("cgroup.subtree_control"), &controllers).desc("Failed to enable cgroup controllers")?;
+
+	Ok(programs)
+}
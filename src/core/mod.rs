@@ -1,27 +1,44 @@
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	fs,
 	sync::{Arc, RwLock, RwLockReadGuard},
-	time::SystemTime,
+	thread,
+	time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{Result, bail};
+use colored::Colorize;
 use log::{error, trace, warn};
+use tokio::sync::broadcast::{self, Receiver, Sender};
 
 use crate::{
-	core::program::{Program, ProgramPtr, Status},
+	core::{
+		event::{Event, Publisher},
+		program::{Program, ProgramPtr, Readiness, RestartPolicy, Status, Termination},
+		schedule::Schedule,
+	},
 	dirs,
 	ext::{PathExt, ResultExt},
-	rlock, wlock,
+	racky_info, racky_warn, rlock, wlock,
 };
 
+pub mod credentials;
+pub mod event;
+pub mod health;
+pub mod limits;
 pub mod program;
+pub mod schedule;
+pub mod watch;
 
 pub type CorePtr = Arc<Core>;
 
+/// Number of events kept for subscribers that fall behind
+const EVENT_CAPACITY: usize = 100;
+
 #[derive(Debug)]
 pub struct Core {
 	programs: RwLock<HashMap<String, ProgramPtr>>,
+	events: Sender<Event>,
 	start_time: SystemTime,
 }
 
@@ -29,13 +46,15 @@ impl Core {
 	pub fn new() -> CorePtr {
 		Arc::new(Self {
 			programs: RwLock::new(HashMap::new()),
+			events: broadcast::channel(EVENT_CAPACITY).0,
 			start_time: SystemTime::now(),
 		})
 	}
 
 	pub fn start(self: &CorePtr) -> Result<(usize, usize)> {
-		let mut total = 0;
-		let mut successful = 0;
+		limits::init();
+
+		let mut programs = Vec::new();
 
 		for entry in fs::read_dir(dirs::config()).desc("Failed to read config directory")? {
 			if let Err(err) = entry {
@@ -53,23 +72,61 @@ impl Core {
 			let program = Program::new(stem);
 			program.load_config();
 
-			if !program.config().auto_start {
-				continue;
+			let restored = program.load_state();
+			let config = program.config();
+
+			if config.auto_start {
+				if config.restart_policy == RestartPolicy::UnlessStopped
+					&& restored == Some(Status::Stopped)
+					&& program.state().stopped_manually
+				{
+					racky_info!(
+						"Program {} will not be started: it was stopped manually and its restart policy is {}",
+						stem.bold(),
+						config.restart_policy.to_string().bold()
+					);
+					self.add_program(&program).ok();
+				} else {
+					programs.push(program);
+				}
+			} else if !config.schedule.is_none() || restored.is_some() {
+				self.add_program(&program).ok();
 			}
+		}
 
-			total += 1;
+		programs.sort_by(|a, b| a.name().cmp(b.name()));
 
-			if self.add_program(&program).and_then(|_| program.start()).is_ok() {
+		let total = programs.len();
+		let mut successful = 0;
+
+		for program in programs {
+			// The program could have already been started as a dependency of another one
+			let program = match self.get_program(program.name()) {
+				Some(existing) => existing,
+				None => {
+					if self.add_program(&program).is_err() {
+						continue;
+					}
+
+					program
+				}
+			};
+
+			if program.is_active() || self.start_program(&program).is_ok() {
 				successful += 1;
 			}
 		}
 
+		self.schedule();
+		watch::monitor(self.clone());
+		health::restart_unhealthy(self.clone());
+
 		Ok((successful, total))
 	}
 
 	pub fn start_program(self: &CorePtr, program: &ProgramPtr) -> Result<()> {
 		let message = match program.status() {
-			Status::Running(_) => Some("Program is already running"),
+			Status::Running(_) | Status::Unhealthy(_) => Some("Program is already running"),
 			Status::Restarting => Some("Program is now restarting"),
 			_ => None,
 		};
@@ -81,10 +138,46 @@ impl Core {
 
 		program.state_mut().attempts.set_current(0);
 		program.load_config();
+
+		let order = self.resolve_dependencies(program).inspect_err(|err| {
+			error!("Program {} could not be started: {err}", program.name());
+		})?;
+
+		for dependency in &order {
+			let config = dependency.config();
+
+			for name in config.depends_on.iter() {
+				let Some(waited) = order.iter().find(|p| p.name() == name) else {
+					bail!("Dependency {name} of program {} was not resolved", dependency.name());
+				};
+
+				self.wait_for(waited, config.depends_wait, config.depends_timeout)
+					.with_desc(|| format!("Dependency {name} of program {} is not ready", dependency.name()))?;
+			}
+
+			if Arc::ptr_eq(dependency, program) {
+				break;
+			}
+
+			if dependency.is_active() {
+				continue;
+			}
+
+			dependency.state_mut().attempts.set_current(0);
+			dependency
+				.start()
+				.with_desc(|| format!("Failed to start dependency {}", dependency.name()))?;
+		}
+
 		program.start()
 	}
 
-	pub fn stop_program(self: &CorePtr, program: &ProgramPtr) -> Result<()> {
+	pub fn stop_program(self: &CorePtr, program: &ProgramPtr) -> Result<Termination> {
+		self.stop_with_dependents(program, true)
+	}
+
+	/// Stops the program after its dependents, which are never considered stopped manually
+	fn stop_with_dependents(self: &CorePtr, program: &ProgramPtr, manual: bool) -> Result<Termination> {
 		if !program.is_active() {
 			warn!(
 				"Program {} could not be stopped: Program is not running",
@@ -93,7 +186,37 @@ impl Core {
 			bail!("Program is not running");
 		}
 
-		program.stop()
+		for dependent in self.get_dependents(program) {
+			if dependent.is_active() {
+				dependent
+					.stop(false)
+					.with_desc(|| format!("Failed to stop dependent program {}", dependent.name()))?;
+			}
+		}
+
+		program.stop(manual)
+	}
+
+	pub fn restart_program(self: &CorePtr, program: &ProgramPtr) -> Result<Termination> {
+		let dependents = self
+			.get_dependents(program)
+			.into_iter()
+			.filter(|dependent| dependent.is_active())
+			.collect::<Vec<_>>();
+
+		let termination = self
+			.stop_with_dependents(program, false)
+			.desc("Failed to stop program")?;
+		self.start_program(program).desc("Failed to start program")?;
+
+		for dependent in dependents.iter().rev() {
+			if !dependent.is_active() {
+				self.start_program(dependent)
+					.with_desc(|| format!("Failed to start dependent program {}", dependent.name()))?;
+			}
+		}
+
+		Ok(termination)
 	}
 
 	pub fn programs<'a>(self: &'a CorePtr) -> RwLockReadGuard<'a, HashMap<String, ProgramPtr>> {
@@ -113,6 +236,7 @@ impl Core {
 			bail!("Program already exists");
 		}
 
+		program.state_mut().publisher = Some(Publisher::new(name, self.events.clone()));
 		programs.insert(name.to_owned(), program.to_owned());
 		trace!("Program {name} added");
 
@@ -123,7 +247,8 @@ impl Core {
 		let name = program.name();
 
 		match wlock!(self.programs).remove(name) {
-			Some(_) => {
+			Some(program) => {
+				program.state_mut().publisher = None;
 				trace!("Program {name} removed");
 				Ok(())
 			}
@@ -134,6 +259,181 @@ impl Core {
 		}
 	}
 
+	/// Returns a receiver of status changes of all programs in core
+	pub fn subscribe(self: &CorePtr) -> Receiver<Event> {
+		self.events.subscribe()
+	}
+
+	/// Spawns a thread that starts programs at their scheduled times
+	fn schedule(self: &CorePtr) {
+		let core = self.clone();
+
+		thread::spawn(move || {
+			let mut schedules = HashMap::<String, Schedule>::new();
+
+			loop {
+				let now = SystemTime::now();
+				let programs = core.programs().values().cloned().collect::<Vec<_>>();
+
+				for program in programs {
+					let name = program.name().to_owned();
+					let schedule = program.config().schedule;
+
+					if schedule.is_none() {
+						if schedules.remove(&name).is_some() {
+							program.state_mut().next_run = None;
+						}
+
+						continue;
+					}
+
+					let next_run = program.state().next_run;
+
+					if schedules.get(&name) == Some(&schedule) {
+						match next_run {
+							Some(next_run) if next_run > now => continue,
+							Some(_) if program.is_active() => racky_warn!(
+								"Program {} skipped its scheduled run: Previous run is still active",
+								name.bold()
+							),
+							Some(_) => {
+								racky_info!("Starting program {} on schedule", name.bold());
+
+								let core = core.clone();
+								let program = program.clone();
+
+								thread::spawn(move || core.start_program(&program).ok());
+							}
+							None => (),
+						}
+					}
+
+					let next_run = schedule.next_after(now);
+
+					if next_run.is_none() {
+						warn!("Program {name} has a schedule that never matches: {schedule}");
+					}
+
+					program.state_mut().next_run = next_run;
+					schedules.insert(name, schedule);
+				}
+
+				schedules.retain(|name, _| core.get_program(name).is_some());
+				thread::sleep(Duration::from_secs(1));
+			}
+		});
+	}
+
+	/// Returns the program with all its dependencies in the order they should be started,
+	/// dependencies that are not yet in core are only added once the whole graph is valid
+	fn resolve_dependencies(self: &CorePtr, program: &ProgramPtr) -> Result<Vec<ProgramPtr>> {
+		fn visit(
+			core: &CorePtr,
+			program: &ProgramPtr,
+			path: &mut Vec<String>,
+			order: &mut Vec<ProgramPtr>,
+		) -> Result<()> {
+			let name = program.name().to_owned();
+
+			if let Some(index) = path.iter().position(|n| n == &name) {
+				bail!("Dependency cycle detected: {} -> {name}", path[index..].join(" -> "));
+			}
+
+			if order.iter().any(|p| p.name() == name) {
+				return Ok(());
+			}
+
+			path.push(name);
+
+			for dependency in program.config().depends_on.iter() {
+				let resolved = order.iter().find(|p| p.name() == dependency).cloned();
+
+				let dependency = match resolved.or_else(|| core.get_program(dependency)) {
+					Some(dependency) => {
+						if !dependency.is_active() {
+							dependency.load_config();
+						}
+
+						dependency
+					}
+					None => {
+						let new = Program::new(dependency);
+
+						if !new.paths().validate() {
+							bail!("Dependency {dependency} of program {} does not exist", program.name());
+						}
+
+						new.load_config();
+						new
+					}
+				};
+
+				visit(core, &dependency, path, order)?;
+			}
+
+			path.pop();
+			order.push(program.clone());
+
+			Ok(())
+		}
+
+		let mut order = Vec::new();
+		visit(self, program, &mut Vec::new(), &mut order)?;
+
+		for dependency in &order {
+			if !Arc::ptr_eq(dependency, program) && self.get_program(dependency.name()).is_none() {
+				self.add_program(dependency)?;
+			}
+		}
+
+		Ok(order)
+	}
+
+	/// Returns programs that depend on the given program in the order they should be stopped
+	fn get_dependents(self: &CorePtr, program: &ProgramPtr) -> Vec<ProgramPtr> {
+		fn visit(programs: &[ProgramPtr], name: &str, order: &mut Vec<ProgramPtr>, visited: &mut HashSet<String>) {
+			for program in programs {
+				if !program.config().depends_on.iter().any(|n| n == name) || !visited.insert(program.name().to_owned())
+				{
+					continue;
+				}
+
+				visit(programs, program.name(), order, visited);
+				order.push(program.clone());
+			}
+		}
+
+		let programs = self.programs().values().cloned().collect::<Vec<_>>();
+		let mut order = Vec::new();
+
+		visit(
+			&programs,
+			program.name(),
+			&mut order,
+			&mut HashSet::from([program.name().to_owned()]),
+		);
+
+		order
+	}
+
+	fn wait_for(self: &CorePtr, program: &ProgramPtr, readiness: Readiness, timeout: usize) -> Result<()> {
+		let deadline = Instant::now() + Duration::from_secs(timeout as u64);
+
+		while !program.is_ready(readiness) {
+			if !program.is_active() {
+				bail!("Program is not running");
+			}
+
+			if Instant::now() >= deadline {
+				bail!("Program did not become {readiness} within {timeout} seconds");
+			}
+
+			thread::sleep(Duration::from_millis(100));
+		}
+
+		Ok(())
+	}
+
 	#[allow(clippy::needless_lifetimes)]
 	pub fn start_time<'a>(self: &'a CorePtr) -> &'a SystemTime {
 		&self.start_time
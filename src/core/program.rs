@@ -1,25 +1,58 @@
+#[cfg(unix)]
+use std::time::Instant;
 use std::{
-	collections::HashMap,
+	collections::{HashMap, VecDeque},
 	fmt::{self, Display, Formatter},
 	fs,
-	path::{Path, PathBuf},
+	ops::Deref,
+	path::{Component, Path, PathBuf},
 	process::{Command as StdCommand, Stdio},
+	str::FromStr,
 	sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard},
 	thread,
 	time::{Duration, SystemTime},
 };
+#[cfg(target_os = "linux")]
+use std::{
+	ffi::OsStr,
+	os::{
+		fd::AsRawFd,
+		unix::{
+			ffi::OsStrExt,
+			fs::{FileTypeExt, OpenOptionsExt},
+		},
+	},
+};
 
-use anyhow::Result;
+#[cfg(unix)]
+use anyhow::bail;
+use anyhow::{Result, anyhow};
 use colored::Colorize;
 use command_group::CommandGroup;
 use config_derive::{Get, Iter, Set, Val};
 use documented::DocumentedFields;
+use jiff::Timestamp;
 use log::{error, info, trace, warn};
+#[cfg(unix)]
+use nix::{errno::Errno, sys::signal, unistd::Pid};
+#[cfg(target_os = "linux")]
+use nix::{
+	fcntl::{FcntlArg, OFlag, fcntl},
+	sys::stat::Mode,
+};
 use serde::{Deserialize, Serialize};
 use toml::{Value, map::Map};
 
+#[cfg(windows)]
+use crate::command::Command;
 use crate::{
-	command::Command,
+	core::{
+		credentials::Credentials,
+		event::Publisher,
+		health::{self, Health, Probe},
+		limits::{Limits, Size},
+		schedule::Schedule,
+	},
 	dirs,
 	ext::{PathExt, ResultExt},
 	logger, racky_error, racky_info, racky_warn, rlock, util, wlock,
@@ -27,6 +60,8 @@ use crate::{
 
 pub type ProgramPtr = Arc<Program>;
 
+const HISTORY_SIZE: usize = 10;
+
 #[derive(Debug, Default)]
 pub struct Program {
 	name: String,
@@ -47,6 +82,15 @@ impl Program {
 		&self.name
 	}
 
+	/// Returns true if the name is a single path component, so the program paths cannot point outside of Racky directories
+	pub fn is_valid_name(name: &str) -> bool {
+		let mut components = Path::new(name).components();
+		matches!(
+			(components.next(), components.next()),
+			(Some(Component::Normal(_)), None)
+		)
+	}
+
 	pub fn paths(&self) -> &Paths {
 		&self.paths
 	}
@@ -67,8 +111,24 @@ impl Program {
 		rlock!(self.state).status.clone()
 	}
 
+	/// Returns true if the program has reached the given readiness
+	pub fn is_ready(&self, readiness: Readiness) -> bool {
+		let state = rlock!(self.state);
+
+		match readiness {
+			Readiness::None => true,
+			Readiness::Running => state.status.is_alive(),
+			Readiness::Healthy => {
+				state.status.is_alive() && (state.config.health_check.is_none() || state.health.is_healthy())
+			}
+		}
+	}
+
 	pub fn is_active(&self) -> bool {
-		matches!(self.status(), Status::Running(_) | Status::Restarting)
+		matches!(
+			self.status(),
+			Status::Running(_) | Status::Unhealthy(_) | Status::Restarting
+		)
 	}
 
 	pub fn load_config(self: &ProgramPtr) {
@@ -106,7 +166,23 @@ impl Program {
 		let mut state = wlock!(self.state);
 
 		for (key, value) in config {
-			let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+			let value = match value {
+				Value::String(value) => value,
+				Value::Array(values) => shell_words::join(
+					values
+						.iter()
+						.map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string())),
+				),
+				value => value.to_string(),
+			};
+
+			let (key, value) = match Config::migrate(&key, &value) {
+				Some((new, value)) => {
+					info!("Setting `{key}` of {} program config migrated to `{new}`", self.name);
+					(new.to_owned(), value)
+				}
+				None => (key, value),
+			};
 
 			if state.config.get(&key).is_none() {
 				state.vars.insert(key, value);
@@ -148,6 +224,12 @@ impl Program {
 	}
 
 	pub fn update_config(self: &ProgramPtr, key: &str, value: &str) -> Result<()> {
+		let (key, value) = match Config::migrate(key, value) {
+			Some((key, value)) => (key, value),
+			None => (key, value.to_owned()),
+		};
+		let (key, value) = (key, value.as_str());
+
 		let mut state = wlock!(self.state);
 
 		let result = if state.config.get(key).is_none() {
@@ -172,6 +254,210 @@ impl Program {
 		result
 	}
 
+	/// Restores the state saved before the server was restarted and returns the status it was saved with
+	pub fn load_state(self: &ProgramPtr) -> Option<Status> {
+		if !self.paths.state.exists() {
+			return None;
+		}
+
+		let snapshot = match fs::read_to_string(&self.paths.state)
+			.desc("Failed to read state")
+			.and_then(|contents| toml::from_str::<Snapshot>(&contents).desc("Failed to parse state"))
+		{
+			Ok(snapshot) => snapshot,
+			Err(err) => {
+				error!("State of program {} could not be loaded: {err}", self.name);
+				return None;
+			}
+		};
+
+		let mut state = wlock!(self.state);
+
+		// Programs that were alive are stopped unless their process can be adopted below
+		state.status = match &snapshot.status {
+			Status::Running(_) | Status::Unhealthy(_) | Status::Restarting => Status::Stopped,
+			status => status.clone(),
+		};
+		state.exit_code = snapshot.exit_code;
+		state.executions = snapshot.executions;
+		state.attempts.set_total(snapshot.attempts);
+		state.runtime.set_total(Duration::from_secs(snapshot.runtime));
+		state.start_time = Tracker::new(
+			snapshot.last_start.map(SystemTime::from),
+			snapshot.first_start.map(SystemTime::from),
+		);
+		state.history = snapshot.history;
+		// States saved by older versions did not tell manual stops apart
+		state.stopped_manually = snapshot.stopped_manually.unwrap_or(snapshot.status == Status::Stopped);
+
+		drop(state);
+
+		info!("State of program {} loaded", self.name);
+
+		#[cfg(target_os = "linux")]
+		if let Some(pid) = snapshot.status.pid()
+			&& self.adopt(pid, snapshot.pgid)
+		{
+			racky_info!("Program {} is still running and was adopted", self.name.bold());
+		}
+
+		Some(snapshot.status)
+	}
+
+	/// Re-attaches to a program process that kept running while the server was restarted
+	#[cfg(target_os = "linux")]
+	fn adopt(self: &ProgramPtr, pid: u32, pgid: Option<u32>) -> bool {
+		let process = PathBuf::from(format!("/proc/{pid}"));
+		let raw_pid = Pid::from_raw(pid as i32);
+
+		if signal::kill(raw_pid, None).is_err() {
+			trace!("Process {pid} of program {} is no longer running", self.name);
+			return false;
+		}
+
+		if let Some(pgid) = pgid
+			&& nix::unistd::getpgid(Some(raw_pid)).ok() != Some(Pid::from_raw(pgid as i32))
+		{
+			trace!(
+				"Process {pid} is no longer in the process group of program {}",
+				self.name
+			);
+			return false;
+		}
+
+		// The PID could have been reused, so make sure it still runs the program executable
+		let executable = fs::canonicalize(&self.paths.executable).unwrap_or_else(|_| self.paths.executable.clone());
+		let cmdline = fs::read(process.join("cmdline")).unwrap_or_default();
+		let is_same = fs::read_link(process.join("exe")).is_ok_and(|exe| exe == executable)
+			|| cmdline
+				.split(|byte| *byte == 0)
+				.map(|arg| Path::new(OsStr::from_bytes(arg)))
+				.any(|arg| arg == executable || arg == self.paths.executable);
+
+		if !is_same {
+			trace!("Process {pid} does not run the executable of program {}", self.name);
+			return false;
+		}
+
+		let mut state = wlock!(self.state);
+		let start_time = state.start_time.current;
+
+		state.set_status(Status::Running(pid));
+		state
+			.start_time
+			.set_current(start_time.or_else(|| Some(SystemTime::now())));
+
+		let index = state.index;
+		self.save_state(&state);
+
+		drop(state);
+
+		// Output can only be captured again if the program still writes to its FIFOs,
+		// programs started by older versions of the server wrote to pipes that are gone
+		let output = self.paths.get_output();
+		let writes_to_fifos = [1, 2]
+			.iter()
+			.zip(&output)
+			.all(|(fd, path)| fs::read_link(process.join(format!("fd/{fd}"))).is_ok_and(|target| &target == path));
+
+		match writes_to_fifos.then(|| self.open_output()) {
+			Some(Ok([stdout, stderr])) => logger::capture(stdout, stderr, &self.paths.logs),
+			Some(Err(err)) => warn!("Output of adopted program {} could not be captured: {err}", self.name),
+			None => warn!("Output of adopted program {} could not be captured", self.name),
+		}
+
+		health::monitor(self.clone(), index);
+
+		let this = self.clone();
+
+		// Adopted processes are not children of this server, so their exit can only be detected by polling
+		thread::spawn(move || {
+			while signal::kill(raw_pid, None).is_ok() {
+				thread::sleep(Duration::from_secs(1));
+
+				if this.state().index() != index {
+					return;
+				}
+			}
+
+			racky_warn!(
+				"Adopted program {} exited, its exit status is unknown",
+				this.name.bold()
+			);
+
+			this.handle_exit(index, Status::Errored(String::from("Exit status unknown")), None);
+		});
+
+		true
+	}
+
+	/// Creates the FIFOs the program writes its output to and returns their read and write ends
+	///
+	/// Unlike pipes, FIFOs can be opened again by the next server. The program gets read-write ends,
+	/// so there always is a reader and it does not get `SIGPIPE` while the server is down,
+	/// its output waits in the FIFO instead (writes block once it is full).
+	#[cfg(target_os = "linux")]
+	fn create_output(&self) -> Result<([fs::File; 2], [fs::File; 2])> {
+		fs::create_dir_all(dirs::state()).desc("Failed to create state directory")?;
+
+		let create = |path: &PathBuf| -> Result<fs::File> {
+			if !fs::metadata(path).is_ok_and(|metadata| metadata.file_type().is_fifo()) {
+				if path.exists() {
+					fs::remove_file(path).desc("Failed to remove output file")?;
+				}
+
+				nix::unistd::mkfifo(path, Mode::S_IRUSR | Mode::S_IWUSR).desc("Failed to create output FIFO")?;
+			}
+
+			fs::OpenOptions::new()
+				.read(true)
+				.write(true)
+				.open(path)
+				.desc("Failed to open output FIFO")
+		};
+
+		let [stdout, stderr] = self.paths.get_output();
+		let writers = [create(&stdout)?, create(&stderr)?];
+
+		Ok((self.open_output()?, writers))
+	}
+
+	/// Opens the read ends of the output FIFOs, which end once no process writes to them anymore
+	#[cfg(target_os = "linux")]
+	fn open_output(&self) -> Result<[fs::File; 2]> {
+		let open = |path: &PathBuf| -> Result<fs::File> {
+			// Opening the read end blocks until there is a writer, which might have exited already
+			let file = fs::OpenOptions::new()
+				.read(true)
+				.custom_flags(OFlag::O_NONBLOCK.bits())
+				.open(path)
+				.desc("Failed to open output FIFO")?;
+
+			fcntl(file.as_raw_fd(), FcntlArg::F_SETFL(OFlag::empty())).desc("Failed to configure output FIFO")?;
+
+			Ok(file)
+		};
+
+		let [stdout, stderr] = self.paths.get_output();
+
+		Ok([open(&stdout)?, open(&stderr)?])
+	}
+
+	/// Persists the state so it survives server restarts, expects the caller to hold the state lock
+	fn save_state(&self, state: &State) {
+		let result = toml::to_string_pretty(&Snapshot::from(state))
+			.desc("Failed to serialize state")
+			.and_then(|contents| {
+				fs::create_dir_all(dirs::state()).desc("Failed to create state directory")?;
+				fs::write(&self.paths.state, contents).desc("Failed to write state")
+			});
+
+		match result {
+			Ok(()) => trace!("State of program {} saved", self.name),
+			Err(err) => warn!("State of program {} could not be saved: {err}", self.name),
+		}
+	}
+
 	pub(super) fn start(self: &ProgramPtr) -> Result<()> {
 		let mut command = if self.paths.executable.get_ext() == "sh" {
 			let mut command = StdCommand::new("bash");
@@ -182,14 +468,55 @@ impl Program {
 		};
 
 		let mut state = wlock!(self.state);
+		let name = self.name.bold();
+		let limits = Limits::new(&self.name, &state.config);
+
+		let result = Credentials::resolve(&state.config.user, &state.config.group).and_then(|credentials| {
+			// Limits have to be applied first as joining the cgroup requires the server privileges
+			limits.apply(&mut command)?;
+
+			if let Some(credentials) = credentials {
+				credentials.apply(&mut command);
+			}
+
+			Ok(())
+		});
+
+		if let Err(err) = result {
+			racky_error!("Program {name} failed to start: {err}");
+			state.set_status(Status::Failed(err.to_string()));
+			self.save_state(&state);
+			return Err(err);
+		}
+
+		#[cfg(target_os = "linux")]
+		let output = match self.create_output() {
+			Ok((readers, [stdout, stderr])) => {
+				command.stdout(stdout).stderr(stderr);
+				Some(readers)
+			}
+			Err(err) => {
+				warn!(
+					"Output FIFOs of program {} are not available, its output will not survive server restarts: {err}",
+					self.name
+				);
+				command.stdout(Stdio::piped()).stderr(Stdio::piped());
+				None
+			}
+		};
+
+		#[cfg(not(target_os = "linux"))]
+		command.stdout(Stdio::piped()).stderr(Stdio::piped());
+
 		let result = command
+			.args(state.config.args.iter())
 			.current_dir(self.paths.get_working_directory())
 			.envs(&state.vars)
-			.stdout(Stdio::piped())
-			.stderr(Stdio::piped())
 			.group_spawn();
 
-		let name = self.name.bold();
+		// The server must not keep the write ends of the FIFOs, or their output would never end
+		drop(command);
+
 		let mut process = match result {
 			Ok(process) => {
 				racky_info!("Program {name} started successfully");
@@ -198,168 +525,530 @@ impl Program {
 			Err(err) => {
 				racky_error!("Program {name} failed to start: {err}");
 				state.set_status(Status::Failed(err.to_string()));
+				self.save_state(&state);
 				return Err(err.into());
 			}
 		};
 
 		state.set_status(Status::Running(process.id()));
 		state.executions += 1;
+		self.save_state(&state);
 
 		let index = state.index;
 		let this = self.clone();
 
 		drop(state);
 
+		// Output is written by the server, so logs stay writable regardless of the program user
+		#[cfg(target_os = "linux")]
+		match output {
+			Some([stdout, stderr]) => logger::capture(stdout, stderr, &self.paths.logs),
+			None => logger::capture_output(process.inner(), &self.paths.logs),
+		}
+
+		#[cfg(not(target_os = "linux"))]
 		logger::capture_output(process.inner(), &self.paths.logs);
+		health::monitor(self.clone(), index);
 		thread::spawn(move || {
-			let status = match process.wait_with_output() {
+			let (status, code) = match process.wait_with_output() {
 				Ok(output) => {
-					if output.status.success() {
+					let code = util::get_exit_code(&output.status);
+
+					let status = if output.status.success() {
 						racky_info!("Program {name} exited successfully");
 						Status::Finished(String::from_utf8_lossy(&output.stdout).to_string())
+					} else if let Some(violation) = limits.get_violation() {
+						if rlock!(this.state).index == index {
+							racky_error!("Program {name} exceeded its resource limits: {violation}");
+						}
+
+						Status::Errored(violation)
+					} else if let Some(signal) = util::get_signal(&output.status) {
+						// Limits like `open_files_limit` or the kernel OOM killer end programs with a signal
+						if rlock!(this.state).index == index {
+							racky_error!("Program {name} was killed by signal {}", signal.bold());
+						}
+
+						Status::Errored(format!("Killed by signal {signal}"))
 					} else {
 						let err = String::from_utf8_lossy(&output.stderr).to_string();
-						let code = util::get_exit_code(&output.status);
 
-						// Ignore SIGTERM
-						if code != 15 {
+						// Ignore exits caused by stopping the program
+						if rlock!(this.state).index == index {
 							racky_error!("Program {name} exited with status code {}", code.to_string().bold());
 						}
 
 						Status::Errored(if err.is_empty() { code.to_string() } else { err })
-					}
+					};
+
+					(status, Some(code))
 				}
 				Err(err) => {
 					racky_error!("Program {name} encountered an unexpected error: {err}");
-					Status::Errored(err.to_string())
+					(Status::Errored(err.to_string()), None)
 				}
 			};
 
-			let success = matches!(status, Status::Finished(_));
-			let mut state = wlock!(this.state);
+			this.handle_exit(index, status, code);
+		});
 
-			if state.index != index {
-				if success {
-					state.attempts.set_current(0);
-				}
-				return;
-			}
+		Ok(())
+	}
 
-			state.set_status(status);
+	/// Updates the status after the program exits and restarts it according to its restart policy
+	fn handle_exit(self: &ProgramPtr, index: usize, status: Status, code: Option<i32>) {
+		let name = self.name.bold();
+		let success = matches!(status, Status::Finished(_));
+		let mut state = wlock!(self.state);
 
-			if !state.config.auto_restart {
-				racky_warn!("Program {name} will not restart: {} disabled", "auto_restart".bold());
-				return;
+		if state.index != index {
+			if success {
+				state.attempts.set_current(0);
 			}
+			return;
+		}
 
-			if state.attempts.current >= state.config.restart_attempts {
-				racky_warn!(
-					"Program {name} will not restart: Maximum number of restart attempts reached: {}",
-					state.attempts.current.to_string().bold()
-				);
-				return;
-			}
+		state.exit_code = code;
+		state.set_status(status);
+		self.save_state(&state);
+
+		if success && !state.config.schedule.is_none() {
+			racky_info!("Program {name} will run again at its next scheduled time");
+			return;
+		}
+
+		// A run that lasted long enough means the program recovered from previous failures
+		if state.runtime.current >= Duration::from_secs(state.config.restart_window as u64) {
+			state.attempts.set_current(0);
+		}
 
-			state.set_status(Status::Restarting);
+		let policy = state.config.restart_policy;
 
+		if policy == RestartPolicy::Never || (policy == RestartPolicy::OnFailure && success) {
 			if success {
 				state.attempts.set_current(0);
-			} else {
-				let current = state.attempts.current + 1;
-				let total = state.attempts.total + 1;
-
-				state.attempts.set_current(current);
-				state.attempts.set_total(total);
 			}
 
 			racky_info!(
-				"Program {name} will restart in {} seconds{}",
-				state.config.restart_delay.to_string().bold(),
-				if state.attempts.current > 0 {
-					format!(
-						". Attempt {}/{}",
-						state.attempts.current.to_string().bold(),
-						state.config.restart_attempts.to_string().bold()
-					)
-				} else {
-					String::new()
-				}
+				"Program {name} will not restart: restart policy is {}",
+				policy.to_string().bold()
+			);
+			return;
+		}
+
+		if !success && state.attempts.current >= state.config.restart_attempts {
+			racky_warn!(
+				"Program {name} will not restart: Maximum number of restart attempts reached: {}",
+				state.attempts.current.to_string().bold()
 			);
+			return;
+		}
 
-			let delay = state.config.restart_delay;
-			drop(state);
+		state.set_status(Status::Restarting);
 
-			thread::sleep(Duration::from_secs(delay as u64));
+		if success {
+			state.attempts.set_current(0);
+		} else {
+			let current = state.attempts.current + 1;
+			let total = state.attempts.total + 1;
 
-			if rlock!(this.state).index == index {
-				this.start().ok();
+			state.attempts.set_current(current);
+			state.attempts.set_total(total);
+		}
+
+		let delay = state.config.get_restart_delay(state.attempts.current);
+
+		racky_info!(
+			"Program {name} will restart in {} seconds{}",
+			format!("{:.1}", delay.as_secs_f64()).bold(),
+			if state.attempts.current > 0 {
+				format!(
+					". Attempt {}/{}",
+					state.attempts.current.to_string().bold(),
+					state.config.restart_attempts.to_string().bold()
+				)
+			} else {
+				String::new()
 			}
-		});
+		);
 
-		Ok(())
+		self.save_state(&state);
+		drop(state);
+
+		thread::sleep(delay);
+
+		if rlock!(self.state).index == index {
+			self.start().ok();
+		}
 	}
 
-	pub(super) fn stop(self: &ProgramPtr) -> Result<()> {
+	/// Stops the program, `manual` tells whether a user asked for it, which `unless-stopped` programs remember
+	pub(super) fn stop(self: &ProgramPtr, manual: bool) -> Result<Termination> {
 		let mut state = wlock!(self.state);
 
-		let pid = if let Status::Running(pid) = &state.status {
-			pid.to_string()
+		let pid = if let Some(pid) = state.status.pid() {
+			pid
 		} else {
 			state.set_status(Status::Stopped);
-			return Ok(());
+			state.stopped_manually = manual;
+			self.save_state(&state);
+			return Ok(Termination::Graceful);
 		};
 		let name = self.name.bold();
+		let config = state.config.clone();
 
 		state.set_status(Status::Stopped);
+		state.stopped_manually = manual;
+		self.save_state(&state);
 
 		drop(state);
 
-		#[cfg(unix)]
-		let result = Command::new("kill").args(["--", &format!("-{}", pid)]).run();
-		#[cfg(windows)]
-		let result = Command::new("taskkill").args(["/f", "/t", "/pid", &pid]).run();
+		let result = Self::terminate(pid, &config);
 
-		match result {
-			Ok(_) => {
-				racky_info!("Program {name} stopped successfully");
-				Ok(())
-			}
-			Err(err) => {
-				racky_error!("Program {name} failed to stop: {err}");
-				Err(err)
+		match &result {
+			Ok(Termination::Graceful) => racky_info!("Program {name} stopped gracefully"),
+			Ok(Termination::Forced) => racky_warn!(
+				"Program {name} did not stop within {} seconds and was killed",
+				config.stop_timeout.to_string().bold()
+			),
+			Err(err) => racky_error!("Program {name} failed to stop: {err}"),
+		}
+
+		result
+	}
+
+	#[cfg(unix)]
+	pub(super) fn terminate(pid: u32, config: &Config) -> Result<Termination> {
+		let group = Pid::from_raw(pid as i32);
+		let is_alive = || !matches!(signal::killpg(group, None), Err(Errno::ESRCH));
+
+		match signal::killpg(group, config.stop_signal.parse::<signal::Signal>()?) {
+			Ok(()) | Err(Errno::ESRCH) => (),
+			Err(err) => bail!("Failed to send {} to process group {pid}: {err}", config.stop_signal),
+		}
+
+		let deadline = Instant::now() + Duration::from_secs(config.stop_timeout as u64);
+
+		while Instant::now() < deadline {
+			if !is_alive() {
+				return Ok(Termination::Graceful);
 			}
+
+			thread::sleep(Duration::from_millis(100));
+		}
+
+		if !is_alive() {
+			return Ok(Termination::Graceful);
+		}
+
+		match signal::killpg(group, signal::Signal::SIGKILL) {
+			Ok(()) | Err(Errno::ESRCH) => Ok(Termination::Forced),
+			Err(err) => bail!("Failed to send SIGKILL to process group {pid}: {err}"),
 		}
 	}
+
+	#[cfg(windows)]
+	pub(super) fn terminate(pid: u32, _config: &Config) -> Result<Termination> {
+		Command::new("taskkill")
+			.args(["/f", "/t", "/pid", &pid.to_string()])
+			.run()
+			.map(|_| Termination::Forced)
+	}
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, DocumentedFields, Val, Iter, Get, Set)]
 pub struct Config {
 	/// Whether to automatically start the program when the Racky server starts
 	pub auto_start: bool,
-	/// Whether to automatically restart the program after it exits
-	pub auto_restart: bool,
-	/// The delay in seconds before restarting the program after it exits
+	/// When to restart the program after it exits: `always`, `on-failure`, `never` or `unless-stopped`
+	pub restart_policy: RestartPolicy,
+	/// The initial delay in seconds before restarting the program after it exits
 	pub restart_delay: usize,
+	/// The maximum delay in seconds before restarting the program
+	pub restart_delay_max: usize,
+	/// The factor the restart delay is multiplied by after each failed attempt
+	pub restart_backoff: f64,
+	/// The random fraction of the restart delay that is added or subtracted from it
+	pub restart_jitter: f64,
+	/// The time in seconds the program has to run for its restart attempts to reset
+	pub restart_window: usize,
 	/// The maximum number of restart attempts after the program exits with an error code
 	pub restart_attempts: usize,
+	/// Command-line arguments passed to the program, quoted the same way as in a shell
+	pub args: List,
+	/// The signal sent to the program's process group to stop it
+	pub stop_signal: Signal,
+	/// The time in seconds to wait for the program to exit before sending SIGKILL
+	pub stop_timeout: usize,
+	/// Health probe: `http://<url>`, `tcp://<host>:<port>` or `exec:<command>` (empty to disable)
+	pub health_check: Probe,
+	/// The interval in seconds between health checks
+	pub health_interval: usize,
+	/// The time in seconds after which a health check is considered failed
+	pub health_timeout: usize,
+	/// The number of consecutive failed health checks after which the program is unhealthy
+	pub health_threshold: usize,
+	/// Whether to restart unhealthy programs, regardless of the restart policy
+	pub health_restart: bool,
+	/// Names of programs that have to be started before this program
+	pub depends_on: List,
+	/// What to wait for after starting dependencies: `none`, `running` or `healthy`
+	pub depends_wait: Readiness,
+	/// The maximum time in seconds to wait for each dependency
+	pub depends_timeout: usize,
+	/// Cron expression (e.g. `0 3 * * *` or `@daily`) at which the program is started (empty to disable)
+	pub schedule: Schedule,
+	/// The maximum amount of memory the program can use (e.g. `512M` or `2G`, empty for no limit), requires cgroups v2
+	pub memory_limit: Size,
+	/// The maximum number of CPU cores the program can use (e.g. `0.5`, 0 for no limit)
+	pub cpu_limit: f64,
+	/// The maximum number of files the program can have open at once (0 for no limit)
+	pub open_files_limit: usize,
+	/// The maximum number of processes the program can run at once (0 for no limit), requires cgroups v2
+	pub processes_limit: usize,
+	/// The user to run the program as, by name or ID (empty for the server user)
+	pub user: String,
+	/// The group to run the program as, by name or ID (empty for the primary group of `user`)
+	pub group: String,
+	/// Whether to restart the program when its files change
+	pub watch: bool,
+	/// Globs of files relative to the program root that trigger a restart (empty for all files)
+	pub watch_include: List,
+	/// Globs of files relative to the program root that are ignored when watching
+	pub watch_ignore: List,
+	/// The time in milliseconds without further changes to wait before restarting
+	pub watch_debounce: usize,
 }
 
 impl Default for Config {
 	fn default() -> Self {
 		Self {
 			auto_start: false,
-			auto_restart: true,
+			restart_policy: RestartPolicy::OnFailure,
 			restart_delay: 3,
+			restart_delay_max: 60,
+			restart_backoff: 2.0,
+			restart_jitter: 0.1,
+			restart_window: 60,
 			restart_attempts: 5,
+			args: List::default(),
+			stop_signal: Signal::default(),
+			stop_timeout: 10,
+			health_check: Probe::None,
+			health_interval: 10,
+			health_timeout: 5,
+			health_threshold: 3,
+			health_restart: false,
+			depends_on: List::default(),
+			depends_wait: Readiness::None,
+			depends_timeout: 30,
+			schedule: Schedule::default(),
+			memory_limit: Size::default(),
+			cpu_limit: 0.0,
+			open_files_limit: 0,
+			processes_limit: 0,
+			user: String::new(),
+			group: String::new(),
+			watch: false,
+			watch_include: List::default(),
+			watch_ignore: List(vec![String::from(".git/**")]),
+			watch_debounce: 500,
 		}
 	}
 }
 
+impl Config {
+	/// Maps a setting that no longer exists to its replacement
+	pub fn migrate(key: &str, value: &str) -> Option<(&'static str, String)> {
+		match key {
+			"auto_restart" => Some((
+				"restart_policy",
+				match value {
+					"false" => RestartPolicy::Never,
+					_ => RestartPolicy::Always,
+				}
+				.to_string(),
+			)),
+			_ => None,
+		}
+	}
+
+	/// Returns the delay before the given restart attempt, with backoff and jitter applied
+	pub fn get_restart_delay(&self, attempt: usize) -> Duration {
+		let base = self.restart_delay as f64 * self.restart_backoff.max(1.0).powi(attempt.saturating_sub(1) as i32);
+		let delay = base.min(self.restart_delay_max.max(self.restart_delay) as f64);
+		let jitter = self.restart_jitter.clamp(0.0, 1.0);
+		let jitter = if jitter > 0.0 {
+			rand::random_range(-jitter..=jitter)
+		} else {
+			0.0
+		};
+
+		Duration::try_from_secs_f64((delay * (1.0 + jitter)).max(0.0)).unwrap_or(Duration::MAX)
+	}
+}
+
 impl From<ConfigValue> for Value {
 	fn from(value: ConfigValue) -> Self {
 		match value {
 			ConfigValue::Bool(value) => Value::Boolean(value),
+			ConfigValue::String(value) => Value::String(value),
 			ConfigValue::Usize(value) => Value::Integer(value as i64),
+			ConfigValue::F64(value) => Value::Float(value),
+			ConfigValue::RestartPolicy(value) => Value::String(value.to_string()),
+			ConfigValue::List(value) => Value::Array(value.0.into_iter().map(Value::String).collect()),
+			ConfigValue::Signal(value) => Value::String(value.0),
+			ConfigValue::Probe(value) => Value::String(value.to_string()),
+			ConfigValue::Readiness(value) => Value::String(value.to_string()),
+			ConfigValue::Schedule(value) => Value::String(value.to_string()),
+			ConfigValue::Size(value) => Value::String(value.to_string()),
+		}
+	}
+}
+
+/// List of strings that is parsed and displayed with shell quoting rules
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct List(Vec<String>);
+
+impl Deref for List {
+	type Target = Vec<String>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl FromStr for List {
+	type Err = shell_words::ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		shell_words::split(s).map(Self)
+	}
+}
+
+impl Display for List {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", shell_words::join(&self.0))
+	}
+}
+
+/// Name of a POSIX signal, normalized to the `SIGNAME` form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Signal(String);
+
+impl Default for Signal {
+	fn default() -> Self {
+		Self(String::from("SIGTERM"))
+	}
+}
+
+impl Deref for Signal {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl FromStr for Signal {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let name = s.trim().to_uppercase();
+		let name = if name.starts_with("SIG") {
+			name
+		} else {
+			format!("SIG{name}")
+		};
+
+		#[cfg(unix)]
+		if name.parse::<signal::Signal>().is_err() {
+			bail!("Unknown signal: {s}");
+		}
+
+		Ok(Self(name))
+	}
+}
+
+impl Display for Signal {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+/// Defines when a program is restarted after it exits
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+	/// Restart after every exit
+	Always,
+	/// Restart only after exiting with an error
+	#[default]
+	OnFailure,
+	/// Never restart
+	Never,
+	/// Like `always`, but programs stopped manually stay stopped after the server restarts
+	UnlessStopped,
+}
+
+impl FromStr for RestartPolicy {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_lowercase().replace('_', "-").as_str() {
+			"always" => Ok(RestartPolicy::Always),
+			"on-failure" => Ok(RestartPolicy::OnFailure),
+			"never" => Ok(RestartPolicy::Never),
+			"unless-stopped" => Ok(RestartPolicy::UnlessStopped),
+			_ => Err(anyhow!(
+				"Expected `always`, `on-failure`, `never` or `unless-stopped`, got `{s}`"
+			)),
+		}
+	}
+}
+
+impl Display for RestartPolicy {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			RestartPolicy::Always => write!(f, "always"),
+			RestartPolicy::OnFailure => write!(f, "on-failure"),
+			RestartPolicy::Never => write!(f, "never"),
+			RestartPolicy::UnlessStopped => write!(f, "unless-stopped"),
+		}
+	}
+}
+
+/// State a program has to reach before its dependents are started
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Readiness {
+	#[default]
+	None,
+	Running,
+	Healthy,
+}
+
+impl FromStr for Readiness {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_lowercase().as_str() {
+			"none" => Ok(Readiness::None),
+			"running" => Ok(Readiness::Running),
+			"healthy" => Ok(Readiness::Healthy),
+			_ => Err(anyhow!("Expected `none`, `running` or `healthy`, got `{s}`")),
+		}
+	}
+}
+
+impl Display for Readiness {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Readiness::None => write!(f, "none"),
+			Readiness::Running => write!(f, "running"),
+			Readiness::Healthy => write!(f, "healthy"),
 		}
 	}
 }
@@ -369,6 +1058,7 @@ pub struct Paths {
 	pub executable: PathBuf,
 	pub config: PathBuf,
 	pub logs: PathBuf,
+	pub state: PathBuf,
 }
 
 impl Paths {
@@ -400,6 +1090,7 @@ impl Paths {
 			executable: Self::from_path(&path).executable,
 			config: dirs::config().join(format!("{name}.toml")),
 			logs: dirs::logs().join(name),
+			state: dirs::state().join(format!("{name}.toml")),
 		}
 	}
 
@@ -407,6 +1098,11 @@ impl Paths {
 		self.executable.exists()
 	}
 
+	/// Returns the paths of the FIFOs the program writes its stdout and stderr to (Linux only)
+	pub fn get_output(&self) -> [PathBuf; 2] {
+		["stdout", "stderr"].map(|stream| self.state.with_extension(stream))
+	}
+
 	pub fn get_program_root(&self) -> PathBuf {
 		if self.executable.get_name() == "racky.sh" {
 			let parent = self.executable.get_parent();
@@ -432,11 +1128,12 @@ impl Paths {
 	}
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Status {
 	#[default]
 	Idle,
 	Running(u32),
+	Unhealthy(u32),
 	Restarting,
 	Stopped,
 	Finished(String),
@@ -444,11 +1141,26 @@ pub enum Status {
 	Failed(String),
 }
 
+impl Status {
+	/// Returns the process ID if the program process is alive
+	pub fn pid(&self) -> Option<u32> {
+		match self {
+			Status::Running(pid) | Status::Unhealthy(pid) => Some(*pid),
+			_ => None,
+		}
+	}
+
+	pub fn is_alive(&self) -> bool {
+		self.pid().is_some()
+	}
+}
+
 impl Display for Status {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
 		match self {
 			Status::Idle => write!(f, "Idle"),
 			Status::Running(pid) => write!(f, "Running ({})", pid),
+			Status::Unhealthy(pid) => write!(f, "Unhealthy ({})", pid),
 			Status::Restarting => write!(f, "Restarting..."),
 			Status::Stopped => write!(f, "Stopped"),
 			Status::Finished(output) => write!(f, "Finished ({})", output),
@@ -458,6 +1170,21 @@ impl Display for Status {
 	}
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Termination {
+	Graceful,
+	Forced,
+}
+
+impl Display for Termination {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Termination::Graceful => write!(f, "gracefully"),
+			Termination::Forced => write!(f, "forcefully"),
+		}
+	}
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Tracker<T> {
 	pub current: T,
@@ -486,39 +1213,81 @@ pub struct State {
 	pub executions: usize,
 	pub attempts: Tracker<usize>,
 	pub start_time: Tracker<Option<SystemTime>>,
+	pub health: Health,
+	pub next_run: Option<SystemTime>,
+	pub exit_code: Option<i32>,
+	pub history: VecDeque<Run>,
+	/// Whether the program was stopped by a user rather than by the server, e.g. as a dependent
+	pub stopped_manually: bool,
+	pub(super) publisher: Option<Publisher>,
 	runtime: Tracker<Duration>,
 	index: usize,
 }
 
 impl State {
 	pub fn set_status(&mut self, status: Status) {
-		let is_running = matches!(status, Status::Running(_));
+		let was_alive = self.status.is_alive();
+		let is_alive = status.is_alive();
 
-		if is_running {
+		if is_alive && !was_alive {
 			let now = Some(SystemTime::now());
 
 			self.start_time.set_current(now);
 			self.runtime.set_current(Duration::ZERO);
+			self.health = Health::default();
+			self.stopped_manually = false;
 
 			if self.start_time.total.is_none() {
 				self.start_time.set_total(now);
 			}
-		} else if let Some(start_time) = self.start_time.current {
+		} else if !is_alive
+			&& was_alive
+			&& let Some(start_time) = self.start_time.current
+		{
 			let elapsed = start_time.elapsed().unwrap_or_default();
 
 			self.runtime.set_current(elapsed);
 			self.runtime.set_total(self.runtime.total.saturating_add(elapsed));
+
+			if self.history.len() >= HISTORY_SIZE {
+				self.history.pop_front();
+			}
+
+			self.history.push_back(Run {
+				started: Timestamp::try_from(start_time).unwrap_or_default(),
+				ended: Timestamp::now(),
+				status: status
+					.to_string()
+					.lines()
+					.next()
+					.unwrap_or_default()
+					.chars()
+					.take(100)
+					.collect(),
+			});
 		}
 
-		if is_running || status == Status::Stopped {
+		if (is_alive && !was_alive) || status == Status::Stopped {
 			self.index += 1;
 		}
 
+		if let Some(publisher) = &self.publisher
+			&& status != self.status
+		{
+			let exit_code = if matches!(status, Status::Finished(_) | Status::Errored(_)) {
+				self.exit_code
+			} else {
+				None
+			};
+
+			publisher.publish(&self.status, &status, exit_code);
+		}
+
 		self.status = status;
 	}
 
 	pub fn get_runtime(&self) -> Tracker<Duration> {
-		let elapsed = if matches!(self.status, Status::Running(_)) {
+		let elapsed = if self.status.is_alive() {
 			self.start_time
 				.current
 				.and_then(|time| time.elapsed().ok())
@@ -532,4 +1301,52 @@ impl State {
 			total: self.runtime.total + elapsed,
 		}
 	}
+
+	pub(super) fn index(&self) -> usize {
+		self.index
+	}
+}
+
+/// Finished run of a program kept in its history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Run {
+	pub started: Timestamp,
+	pub ended: Timestamp,
+	pub status: String,
+}
+
+/// Part of the program state that is persisted across server restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Snapshot {
+	status: Status,
+	exit_code: Option<i32>,
+	executions: usize,
+	attempts: usize,
+	runtime: u64,
+	first_start: Option<Timestamp>,
+	last_start: Option<Timestamp>,
+	history: VecDeque<Run>,
+	pgid: Option<u32>,
+	stopped_manually: Option<bool>,
+}
+
+impl From<&State> for Snapshot {
+	fn from(state: &State) -> Self {
+		let timestamp = |time: Option<SystemTime>| time.and_then(|time| Timestamp::try_from(time).ok());
+
+		Self {
+			status: state.status.clone(),
+			exit_code: state.exit_code,
+			executions: state.executions,
+			attempts: state.attempts.total,
+			runtime: state.runtime.total.as_secs(),
+			first_start: timestamp(state.start_time.total),
+			last_start: timestamp(state.start_time.current),
+			history: state.history.clone(),
+			// Programs are spawned as leaders of their own process group
+			pgid: state.status.pid(),
+			stopped_manually: Some(state.stopped_manually),
+		}
+	}
 }
@@ -0,0 +1,302 @@
+use std::{
+	fmt::{self, Display, Formatter},
+	str::FromStr,
+	time::SystemTime,
+};
+
+use anyhow::{Context, Result, bail};
+use jiff::{Timestamp, ToSpan, civil::DateTime, tz::TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::ext::ResultExt;
+
+const MONTHS: [&str; 12] = [
+	"jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Cron expression with five fields (minute, hour, day of month, month, day of week)
+/// or one of the `@yearly`, `@monthly`, `@weekly`, `@daily` and `@hourly` shortcuts
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+	expression: String,
+	fields: Option<Fields>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Fields {
+	minutes: u64,
+	hours: u64,
+	days: u64,
+	months: u64,
+	weekdays: u64,
+	any_day: bool,
+	any_weekday: bool,
+}
+
+impl Schedule {
+	pub fn is_none(&self) -> bool {
+		self.fields.is_none()
+	}
+
+	/// Returns the first matching time after the given time in the local time zone
+	pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+		let fields = self.fields.as_ref()?;
+		let tz = TimeZone::system();
+
+		let start = Timestamp::try_from(time).ok()?.to_zoned(tz.clone()).datetime();
+		let mut current = start.with().second(0).subsec_nanosecond(0).build().ok()?;
+		current = current.checked_add(1.minute()).ok()?;
+
+		while current.year() <= start.year() + 5 {
+			if !contains(fields.months, current.month()) {
+				current = current
+					.date()
+					.first_of_month()
+					.checked_add(1.month())
+					.ok()?
+					.at(0, 0, 0, 0);
+			} else if !fields.matches_day(current) {
+				current = current.date().tomorrow().ok()?.at(0, 0, 0, 0);
+			} else if !contains(fields.hours, current.hour()) {
+				current = current.with().minute(0).build().ok()?.checked_add(1.hour()).ok()?;
+			} else if !contains(fields.minutes, current.minute()) {
+				current = current.checked_add(1.minute()).ok()?;
+			} else {
+				return current
+					.to_zoned(tz)
+					.ok()
+					.map(|zoned| SystemTime::from(zoned.timestamp()));
+			}
+		}
+
+		None
+	}
+}
+
+impl Fields {
+	fn matches_day(&self, time: DateTime) -> bool {
+		let day = contains(self.days, time.day());
+		let weekday = contains(self.weekdays, time.weekday().to_sunday_zero_offset());
+
+		// Same as in cron, if both fields are restricted, matching either of them is enough
+		match (self.any_day, self.any_weekday) {
+			(true, true) => true,
+			(true, false) => weekday,
+			(false, true) => day,
+			(false, false) => day || weekday,
+		}
+	}
+}
+
+impl FromStr for Schedule {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let expression = s.trim().to_owned();
+
+		if expression.is_empty() {
+			return Ok(Self::default());
+		}
+
+		let expanded = match expression.as_str() {
+			"@yearly" | "@annually" => "0 0 1 1 *",
+			"@monthly" => "0 0 1 * *",
+			"@weekly" => "0 0 * * 0",
+			"@daily" | "@midnight" => "0 0 * * *",
+			"@hourly" => "0 * * * *",
+			other => other,
+		};
+
+		let parts = expanded.split_whitespace().collect::<Vec<_>>();
+
+		if parts.len() != 5 {
+			bail!("Cron expression must have 5 fields, got {}", parts.len());
+		}
+
+		let fields = Fields {
+			minutes: parse_field(parts[0], 0, 59, &[]).desc("Invalid minute field")?,
+			hours: parse_field(parts[1], 0, 23, &[]).desc("Invalid hour field")?,
+			days: parse_field(parts[2], 1, 31, &[]).desc("Invalid day of month field")?,
+			months: parse_field(parts[3], 1, 12, &MONTHS).desc("Invalid month field")?,
+			weekdays: {
+				let weekdays = parse_field(parts[4], 0, 7, &WEEKDAYS).desc("Invalid day of week field")?;
+				// Both 0 and 7 stand for Sunday
+				weekdays | (weekdays >> 7 & 1)
+			},
+			any_day: parts[2] == "*",
+			any_weekday: parts[4] == "*",
+		};
+
+		Ok(Self {
+			expression,
+			fields: Some(fields),
+		})
+	}
+}
+
+impl TryFrom<String> for Schedule {
+	type Error = anyhow::Error;
+
+	fn try_from(value: String) -> Result<Self, Self::Error> {
+		value.parse()
+	}
+}
+
+impl From<Schedule> for String {
+	fn from(value: Schedule) -> Self {
+		value.expression
+	}
+}
+
+impl Display for Schedule {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.expression)
+	}
+}
+
+fn contains(set: u64, value: i8) -> bool {
+	set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u8, max: u8, names: &[&str]) -> Result<u64> {
+	let parse_value = |value: &str| -> Result<u8> {
+		let lowercase = value.to_lowercase();
+
+		let value = match names.iter().position(|name| *name == lowercase) {
+			Some(index) => index as u8 + min,
+			None => value.parse().with_context(|| format!("`{value}` is not a number"))?,
+		};
+
+		if value < min || value > max {
+			bail!("{value} is out of range {min}-{max}");
+		}
+
+		Ok(value)
+	};
+
+	let mut set = 0;
+
+	for item in field.split(',') {
+		let (range, step) = match item.split_once('/') {
+			Some((range, step)) => (range, step.parse::<u8>().desc("Invalid step")?),
+			None => (item, 1),
+		};
+
+		if step == 0 {
+			bail!("Step cannot be zero");
+		}
+
+		let (start, end) = if range == "*" {
+			(min, max)
+		} else if let Some((start, end)) = range.split_once('-') {
+			let (start, end) = (parse_value(start)?, parse_value(end)?);
+
+			// Allow ranges like `mon-sun` where Sunday is the last day
+			if end == 0 && names == WEEKDAYS {
+				(start, 7)
+			} else {
+				(start, end)
+			}
+		} else {
+			let value = parse_value(range)?;
+			(value, if item.contains('/') { max } else { value })
+		};
+
+		if start > end {
+			bail!("Invalid range {start}-{end}");
+		}
+
+		for value in (start..=end).step_by(step as usize) {
+			set |= 1 << value;
+		}
+	}
+
+	Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn time(s: &str) -> SystemTime {
+		let datetime: DateTime = s.parse().unwrap();
+		SystemTime::from(datetime.to_zoned(TimeZone::system()).unwrap().timestamp())
+	}
+
+	fn next(expression: &str, after: &str) -> Option<SystemTime> {
+		expression.parse::<Schedule>().unwrap().next_after(time(after))
+	}
+
+	#[test]
+	fn parses_fields() {
+		assert_eq!(parse_field("*", 0, 59, &[]).unwrap(), (1 << 60) - 1);
+		assert_eq!(parse_field("5", 0, 59, &[]).unwrap(), 1 << 5);
+		assert_eq!(parse_field("1,3-5", 0, 59, &[]).unwrap(), 0b111010);
+		assert_eq!(
+			parse_field("*/15", 0, 59, &[]).unwrap(),
+			1 | 1 << 15 | 1 << 30 | 1 << 45
+		);
+		assert_eq!(parse_field("10/20", 0, 59, &[]).unwrap(), 1 << 10 | 1 << 30 | 1 << 50);
+		assert_eq!(parse_field("jan,MAR", 1, 12, &MONTHS).unwrap(), 1 << 1 | 1 << 3);
+		assert_eq!(
+			parse_field("fri-sun", 0, 7, &WEEKDAYS).unwrap(),
+			1 << 5 | 1 << 6 | 1 << 7
+		);
+	}
+
+	#[test]
+	fn rejects_invalid_fields() {
+		assert!(parse_field("60", 0, 59, &[]).is_err());
+		assert!(parse_field("0", 1, 31, &[]).is_err());
+		assert!(parse_field("5-1", 0, 59, &[]).is_err());
+		assert!(parse_field("*/0", 0, 59, &[]).is_err());
+		assert!(parse_field("abc", 0, 59, &[]).is_err());
+		assert!(parse_field("", 0, 59, &[]).is_err());
+	}
+
+	#[test]
+	fn parses_expressions() {
+		assert!("".parse::<Schedule>().unwrap().is_none());
+		assert!(!"@daily".parse::<Schedule>().unwrap().is_none());
+		assert!("* * * *".parse::<Schedule>().is_err());
+		assert!("* * * * * *".parse::<Schedule>().is_err());
+		assert!("@sometimes".parse::<Schedule>().is_err());
+		assert_eq!(" 0 0 * * 0 ".parse::<Schedule>().unwrap().to_string(), "0 0 * * 0");
+	}
+
+	#[test]
+	fn treats_zero_and_seven_as_sunday() {
+		let zero = "0 0 * * 0".parse::<Schedule>().unwrap().fields.unwrap();
+		let seven = "0 0 * * 7".parse::<Schedule>().unwrap().fields.unwrap();
+
+		assert!(contains(zero.weekdays, 0));
+		assert!(contains(seven.weekdays, 0));
+	}
+
+	#[test]
+	fn finds_next_run() {
+		assert_eq!(next("* * * * *", "2030-01-15 10:20:30"), Some(time("2030-01-15 10:21")));
+		assert_eq!(next("30 * * * *", "2030-01-15 10:30"), Some(time("2030-01-15 11:30")));
+		assert_eq!(next("@daily", "2030-01-15 10:20"), Some(time("2030-01-16 00:00")));
+		assert_eq!(next("@yearly", "2030-01-15 10:20"), Some(time("2031-01-01 00:00")));
+		assert_eq!(
+			next("0 9 * * mon-fri", "2030-01-18 10:00"),
+			Some(time("2030-01-21 09:00"))
+		);
+		assert_eq!(next("0 0 31 * *", "2030-02-01 00:00"), Some(time("2030-03-31 00:00")));
+	}
+
+	#[test]
+	fn matches_either_restricted_day_field() {
+		// 2030-01-15 is a Tuesday, so the next Friday comes before the 20th
+		assert_eq!(next("0 0 20 * fri", "2030-01-15 00:00"), Some(time("2030-01-18 00:00")));
+		assert_eq!(next("0 0 16 * fri", "2030-01-15 00:00"), Some(time("2030-01-16 00:00")));
+	}
+
+	#[test]
+	fn never_matching_schedule_has_no_next_run() {
+		assert_eq!(next("0 0 30 feb *", "2030-01-15 00:00"), None);
+	}
+}
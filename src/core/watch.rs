@@ -0,0 +1,257 @@
+use std::{
+	collections::HashMap,
+	path::{Path, PathBuf},
+	sync::mpsc::{self, RecvTimeoutError},
+	thread,
+	time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use colored::Colorize;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use log::{error, trace, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+	core::{
+		CorePtr,
+		program::{List, ProgramPtr},
+	},
+	ext::{PathExt, ResultExt},
+	logger, racky_error, racky_info,
+};
+
+/// Files of a single watched program
+struct Watched {
+	root: PathBuf,
+	include: List,
+	ignore: List,
+	matcher: Option<(GlobSet, GlobSet)>,
+	changed: Option<(Instant, PathBuf)>,
+}
+
+impl Watched {
+	fn new(program: &ProgramPtr) -> Self {
+		let config = program.config();
+		let mut watched = Self {
+			root: program.paths().get_program_root(),
+			include: config.watch_include.clone(),
+			ignore: config.watch_ignore.clone(),
+			matcher: None,
+			changed: None,
+		};
+
+		match build_set(&watched.include).and_then(|include| Ok((include, build_set(&watched.ignore)?))) {
+			Ok(matcher) => watched.matcher = Some(matcher),
+			Err(err) => racky_error!("Program {} has invalid watch globs: {err}", program.name().bold()),
+		}
+
+		watched
+	}
+
+	/// Returns the path relative to the program root if it should trigger a restart
+	fn matches<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+		let (include, ignore) = self.matcher.as_ref()?;
+
+		// Programs consisting of a single file only care about that file
+		if !self.root.is_dir() {
+			return (path == self.root).then(|| Path::new(path.get_name()));
+		}
+
+		let relative = path.strip_prefix(&self.root).ok()?;
+
+		if (self.include.is_empty() || include.is_match(relative)) && !ignore.is_match(relative) {
+			Some(relative)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the path that has to be watched and how
+	fn target(&self) -> (PathBuf, RecursiveMode) {
+		if self.root.is_dir() {
+			(self.root.clone(), RecursiveMode::Recursive)
+		} else {
+			// Editors often replace files instead of modifying them, so the parent directory is watched
+			(self.root.get_parent().to_owned(), RecursiveMode::NonRecursive)
+		}
+	}
+}
+
+/// Restarts programs with `watch` enabled after their files change
+pub fn monitor(core: CorePtr) {
+	let (tx, rx) = mpsc::channel::<notify::Result<Event>>();
+
+	let mut watcher = match notify::recommended_watcher(tx) {
+		Ok(watcher) => watcher,
+		Err(err) => {
+			error!("Failed to create file watcher, programs will not be restarted on changes: {err}");
+			return;
+		}
+	};
+
+	thread::spawn(move || {
+		let mut programs = HashMap::<String, Watched>::new();
+		let mut targets = HashMap::<PathBuf, RecursiveMode>::new();
+
+		loop {
+			sync_programs(&core, &mut programs);
+			sync_targets(&mut watcher, &programs, &mut targets);
+
+			let deadline = Instant::now() + Duration::from_secs(1);
+
+			// Collect events for about a second before checking which programs should be restarted
+			loop {
+				let timeout = deadline.saturating_duration_since(Instant::now());
+
+				let event = match rx.recv_timeout(timeout) {
+					Ok(Ok(event)) => event,
+					Ok(Err(err)) => {
+						warn!("File watcher error: {err}");
+						continue;
+					}
+					Err(RecvTimeoutError::Timeout) => break,
+					Err(RecvTimeoutError::Disconnected) => return,
+				};
+
+				if matches!(event.kind, EventKind::Access(_)) {
+					continue;
+				}
+
+				for path in &event.paths {
+					for watched in programs.values_mut() {
+						if let Some(relative) = watched.matches(path) {
+							watched.changed = Some((Instant::now(), relative.to_owned()));
+						}
+					}
+				}
+			}
+
+			for (name, watched) in &mut programs {
+				let program = match core.get_program(name) {
+					Some(program) => program,
+					None => continue,
+				};
+
+				let debounce = Duration::from_millis(program.config().watch_debounce as u64);
+
+				match &watched.changed {
+					Some((time, path)) if time.elapsed() >= debounce => {
+						restart(&core, &program, path);
+						watched.changed = None;
+					}
+					_ => (),
+				}
+			}
+		}
+	});
+}
+
+/// Starts tracking programs that enabled `watch` and stops tracking the ones that disabled it
+fn sync_programs(core: &CorePtr, programs: &mut HashMap<String, Watched>) {
+	let current = core.programs().values().cloned().collect::<Vec<_>>();
+
+	programs.retain(|name, _| {
+		current
+			.iter()
+			.any(|program| program.name() == name && program.config().watch)
+	});
+
+	for program in current {
+		let config = program.config();
+
+		if !config.watch {
+			continue;
+		}
+
+		let outdated = programs.get(program.name()).is_none_or(|watched| {
+			watched.include != config.watch_include
+				|| watched.ignore != config.watch_ignore
+				|| watched.root != program.paths().get_program_root()
+		});
+
+		if outdated {
+			trace!("Watching files of program {}", program.name());
+			programs.insert(program.name().to_owned(), Watched::new(&program));
+		}
+	}
+}
+
+/// Updates the watcher so it only watches paths of tracked programs
+fn sync_targets(
+	watcher: &mut RecommendedWatcher,
+	programs: &HashMap<String, Watched>,
+	targets: &mut HashMap<PathBuf, RecursiveMode>,
+) {
+	let mut wanted = HashMap::<PathBuf, RecursiveMode>::new();
+
+	for watched in programs.values() {
+		let (path, mode) = watched.target();
+
+		if mode == RecursiveMode::Recursive || !wanted.contains_key(&path) {
+			wanted.insert(path, mode);
+		}
+	}
+
+	targets.retain(|path, mode| {
+		if wanted.get(path) == Some(mode) {
+			return true;
+		}
+
+		watcher.unwatch(path).ok();
+		false
+	});
+
+	for (path, mode) in wanted {
+		if targets.contains_key(&path) {
+			continue;
+		}
+
+		match watcher.watch(&path, mode) {
+			Ok(()) => {
+				targets.insert(path, mode);
+			}
+			Err(err) => warn!("Failed to watch {path:?}: {err}"),
+		}
+	}
+}
+
+fn restart(core: &CorePtr, program: &ProgramPtr, path: &Path) {
+	let name = program.name().bold();
+
+	if !program.is_active() {
+		trace!("Program {name} changed but it is not running");
+		return;
+	}
+
+	let message = format!("Change detected in {}, restarting program", path.display());
+
+	racky_info!("Program {name}: {message}");
+
+	if let Err(err) = logger::append(&program.paths().logs, &message) {
+		warn!("Failed to write watch activity to {name} logs: {err}");
+	}
+
+	let core = core.clone();
+	let program = program.clone();
+
+	thread::spawn(move || {
+		if let Err(err) = core.restart_program(&program) {
+			racky_error!(
+				"Program {} could not be restarted after a change: {err}",
+				program.name().bold()
+			);
+			logger::append(&program.paths().logs, &format!("Restart after a change failed: {err}")).ok();
+		}
+	});
+}
+
+fn build_set(globs: &List) -> Result<GlobSet> {
+	let mut builder = GlobSetBuilder::new();
+
+	for glob in globs.iter() {
+		builder.add(Glob::new(glob).with_desc(|| format!("Invalid glob `{glob}`"))?);
+	}
+
+	builder.build().desc("Failed to build glob set")
+}
@@ -20,6 +20,11 @@ pub fn racky() -> PathBuf {
 	HOME_DIR.join(".racky")
 }
 
+#[inline]
+pub fn audit() -> PathBuf {
+	racky().join("audit")
+}
+
 #[inline]
 pub fn bin() -> PathBuf {
 	racky().join("bin")
@@ -34,3 +39,14 @@ pub fn config() -> PathBuf {
 pub fn logs() -> PathBuf {
 	racky().join("logs")
 }
+
+/// Unix socket of the server, kept in a directory only the user running the server can access
+#[inline]
+pub fn socket() -> PathBuf {
+	racky().join("socket").join("racky.sock")
+}
+
+#[inline]
+pub fn state() -> PathBuf {
+	racky().join("state")
+}
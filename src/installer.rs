@@ -20,6 +20,8 @@ After=network.target\n\
 ExecStart=/home/$1/.racky/bin/racky server start -vvvv -y\n\
 Environment=HOME=/home/$1 SUDO_USER=$1\n\
 Restart=always\n\
+Delegate=yes\n\
+$2\
 \n\
 [Install]\n\
 WantedBy=default.target\n";
@@ -81,9 +83,26 @@ pub fn install(server: bool, force: bool) -> Result<()> {
 		fs::create_dir_all(service_dir).desc("Failed to create service directory")?;
 	}
 
-	if !service_path.exists() || force {
-		fs::write(&service_path, SYSTEMD_SERVICE.replace("$1", &util::get_user()?))
-			.desc("Failed to create service file")?;
+	// Programs are orphaned when the service stops only if the user opted in, as they are not tracked until
+	// the server starts again
+	let service = SYSTEMD_SERVICE.replace("$1", &util::get_user()?).replace(
+		"$2",
+		if Config::new().keep_programs {
+			"KillMode=process\n"
+		} else {
+			""
+		},
+	);
+
+	// Service files written by older versions are replaced, so they do not keep outdated settings
+	if force || fs::read_to_string(&service_path).ok().as_deref() != Some(service.as_str()) {
+		let existed = service_path.exists();
+
+		fs::write(&service_path, service).desc("Failed to create service file")?;
+
+		if existed && let Err(err) = Command::new("systemctl").arg("daemon-reload").run() {
+			racky_warn!("Failed to reload systemd units: {err}! Try running `sudo systemctl daemon-reload` manually");
+		}
 	}
 
 	match Command::new("systemctl").args(["enable", &service_name]).run() {
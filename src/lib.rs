@@ -1,5 +1,7 @@
 #![allow(clippy::new_without_default)]
 
+pub mod audit;
+pub mod auth;
 pub mod cli;
 pub mod client;
 pub mod command;
@@ -11,6 +13,8 @@ pub mod ext;
 pub mod installer;
 pub mod logger;
 pub mod servers;
+pub mod tls;
+pub mod tokens;
 pub mod util;
 pub mod web;
 pub mod zip;
@@ -1,11 +1,12 @@
 use std::{
 	fmt::{self, Display, Formatter},
-	fs::{self, File},
-	io::{BufRead, BufReader, Write},
-	path::Path,
+	fs::{self, File, OpenOptions},
+	io::{BufRead, BufReader, Read, Write},
+	path::{Path, PathBuf},
 	process::Child,
 	sync::mpsc,
 	thread,
+	time::Duration,
 };
 
 use anyhow::{Context, Result};
@@ -19,6 +20,7 @@ use env_logger::{Builder, WriteStyle};
 use lazy_static::lazy_static;
 use log::{Level, LevelFilter};
 use regex::Regex;
+use tokio::{runtime::Handle, sync::mpsc::Sender, time};
 
 use crate::{
 	config::Config,
@@ -135,6 +137,16 @@ pub fn prompt(prompt: &str, default: bool) -> bool {
 pub fn capture_output(process: &mut Child, path: &Path) {
 	let stdout = process.stdout.take().unwrap();
 	let stderr = process.stderr.take().unwrap();
+
+	capture(stdout, stderr, path);
+}
+
+/// Writes lines read from both streams to log files in the given directory until both of them end
+pub fn capture<O, E>(stdout: O, stderr: E, path: &Path)
+where
+	O: Read + Send + 'static,
+	E: Read + Send + 'static,
+{
 	let path = path.to_owned();
 
 	let (tx_out, rx) = mpsc::channel::<Option<String>>();
@@ -195,38 +207,175 @@ pub fn read_file(path: &Path, index: usize) -> Result<String> {
 	fs::read_to_string(log).with_desc(|| format!("Failed to read {}/{} file", path.get_stem(), log.get_name()))
 }
 
-fn write_file(message: &str, file: &mut Option<File>, size: &mut usize, path: &Path) -> Result<()> {
-	let config = Config::new();
-	let current_file = match file {
-		Some(file) => file,
-		None => {
-			if !path.exists() {
-				fs::create_dir_all(path)?;
+/// Sends the last lines of the latest log file in the given directory and then every new line,
+/// switching to newer files as logs rotate, until the receiver is dropped.
+/// Must run on a blocking thread of the runtime, like the ones of `spawn_blocking`
+pub fn follow(path: &Path, lines: usize, tx: Sender<String>) {
+	let mut current = None;
+	let mut reader = None;
+	let mut buffer = String::new();
+
+	while !tx.is_closed() {
+		let latest = get_latest(path);
+
+		if latest.is_some() && latest != current {
+			// Lines written to the previous file right before it was rotated would be lost otherwise,
+			// a partial line at its end is never finished, so it is sent as it is
+			if let Some(previous) = &mut reader {
+				if !send_lines(previous, &mut buffer, &tx) {
+					return;
+				}
+
+				if !buffer.is_empty()
+					&& tx
+						.blocking_send(buffer.trim_end_matches(['\n', '\r']).to_owned())
+						.is_err()
+				{
+					return;
+				}
+
+				buffer.clear();
 			}
 
-			if config.log_file_limit > 0 {
-				let logs = fs::read_dir(path)?.collect::<Vec<_>>();
-				let diff = (logs.len() + 1).saturating_sub(config.log_file_limit);
+			let Some(file) = latest.as_ref().and_then(|latest| File::open(latest).ok()) else {
+				if !wait(&tx) {
+					return;
+				}
+
+				continue;
+			};
+
+			let mut file = BufReader::new(file);
+			buffer.clear();
 
-				if diff > 0 {
-					let mut logs = logs
-						.iter()
-						.filter_map(|log| log.as_ref().ok().map(|entry| entry.path()))
-						.collect::<Vec<_>>();
+			// Only the first file is shortened, rotated ones are sent whole
+			if current.is_none() {
+				file.read_to_string(&mut buffer).ok();
 
-					logs.sort_by(|a, b| a.get_stem().cmp(b.get_stem()));
+				let partial = if buffer.ends_with('\n') {
+					String::new()
+				} else {
+					buffer
+						.rsplit_once('\n')
+						.map(|(_, partial)| partial)
+						.unwrap_or(&buffer)
+						.to_owned()
+				};
 
-					for log in logs.iter().take(diff) {
-						fs::remove_file(log)?;
+				let complete = &buffer[..buffer.len() - partial.len()];
+				let skip = complete.lines().count().saturating_sub(lines);
+
+				for line in complete.lines().skip(skip) {
+					if tx.blocking_send(line.to_owned()).is_err() {
+						return;
 					}
 				}
+
+				buffer = partial;
 			}
 
-			let path = path.join(format!("{}.log", util::timestamp(None).replace(":", "-")));
+			current = latest;
+			reader = Some(file);
+		}
+
+		if let Some(reader) = &mut reader
+			&& !send_lines(reader, &mut buffer, &tx)
+		{
+			return;
+		}
+
+		if !wait(&tx) {
+			return;
+		}
+	}
+}
+
+/// Waits before checking the log files again, returns `false` as soon as the receiver is dropped
+fn wait(tx: &Sender<String>) -> bool {
+	Handle::current().block_on(async { time::timeout(Duration::from_millis(250), tx.closed()).await.is_err() })
+}
+
+/// Sends complete lines until the end of the file, returns `false` once the receiver is dropped
+fn send_lines(reader: &mut BufReader<File>, buffer: &mut String, tx: &Sender<String>) -> bool {
+	while let Ok(read) = reader.read_line(buffer) {
+		// Partial lines are kept until the writer finishes them
+		if read == 0 || !buffer.ends_with('\n') {
+			break;
+		}
 
-			*file = Some(File::create(path)?);
-			file.as_mut().unwrap()
+		if tx
+			.blocking_send(buffer.trim_end_matches(['\n', '\r']).to_owned())
+			.is_err()
+		{
+			return false;
 		}
+
+		buffer.clear();
+	}
+
+	true
+}
+
+/// Appends a message from Racky to the latest log file in the given directory
+pub fn append(path: &Path, message: &str) -> Result<()> {
+	let message = format!("[{}] [racky] {message}", util::timestamp(None));
+
+	match get_latest(path) {
+		Some(latest) => {
+			let mut file = OpenOptions::new().append(true).open(latest)?;
+			writeln!(file, "{message}")?;
+		}
+		None => write_file(&message, &mut None, &mut 0, path)?,
+	}
+
+	Ok(())
+}
+
+fn get_latest(path: &Path) -> Option<PathBuf> {
+	fs::read_dir(path).ok().and_then(|entries| {
+		entries
+			.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+			.max_by(|a, b| a.get_stem().cmp(b.get_stem()))
+	})
+}
+
+/// Creates a new log file in the given directory, removing the oldest ones over the file limit
+pub fn create_file(path: &Path) -> Result<File> {
+	let config = Config::new();
+
+	if !path.exists() {
+		fs::create_dir_all(path)?;
+	}
+
+	if config.log_file_limit > 0 {
+		let logs = fs::read_dir(path)?.collect::<Vec<_>>();
+		let diff = (logs.len() + 1).saturating_sub(config.log_file_limit);
+
+		if diff > 0 {
+			let mut logs = logs
+				.iter()
+				.filter_map(|log| log.as_ref().ok().map(|entry| entry.path()))
+				.collect::<Vec<_>>();
+
+			logs.sort_by(|a, b| a.get_stem().cmp(b.get_stem()));
+
+			for log in logs.iter().take(diff) {
+				fs::remove_file(log)?;
+			}
+		}
+	}
+
+	let path = path.join(format!("{}.log", util::timestamp(None).replace(":", "-")));
+
+	// Appending lets other writers add lines to the same file, see `append`
+	Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
+
+fn write_file(message: &str, file: &mut Option<File>, size: &mut usize, path: &Path) -> Result<()> {
+	let config = Config::new();
+	let current_file = match file {
+		Some(file) => file,
+		None => file.insert(create_file(path)?),
 	};
 
 	let mut message = strip_ansi_escapes::strip_str(message);
@@ -261,12 +410,15 @@ impl Table {
 		}
 	}
 
+	/// Adds a row, cells with several lines make the row span as many lines
 	pub fn add_row(&mut self, row: Vec<String>) {
 		for (i, column) in row.iter().enumerate() {
+			let width = column.lines().map(str::len).max().unwrap_or_default();
+
 			if self.columns.len() <= i {
-				self.columns.push(column.len());
-			} else if self.columns[i] < column.len() {
-				self.columns[i] = column.len();
+				self.columns.push(width);
+			} else if self.columns[i] < width {
+				self.columns[i] = width;
 			}
 		}
 
@@ -294,11 +446,20 @@ impl Display for Table {
 		write!(f, "{header}|\n{separator}|\n")?;
 
 		for row in self.rows.iter().skip(1) {
-			for (i, column) in row.iter().enumerate() {
-				write!(f, "| {0: <1$} ", column, self.columns[i])?;
-			}
+			let height = row
+				.iter()
+				.map(|column| column.lines().count())
+				.max()
+				.unwrap_or_default();
+
+			for line in 0..height.max(1) {
+				for (i, column) in row.iter().enumerate() {
+					let column = column.lines().nth(line).unwrap_or_default();
+					write!(f, "| {0: <1$} ", column, self.columns[i])?;
+				}
 
-			writeln!(f, "|")?;
+				writeln!(f, "|")?;
+			}
 		}
 
 		Ok(())
@@ -1,20 +1,66 @@
-use std::{collections::HashMap, fs};
+use std::{
+	collections::{BTreeSet, HashMap},
+	fs,
+	net::IpAddr,
+};
 
-use anyhow::{Context, Error, Result};
+use anyhow::{Error, Result, bail, ensure};
+use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use toml::Table;
 
 use crate::{dirs, ext::ResultExt};
 
+/// Version of the servers file format, files without a version were written before tags were added
+const VERSION: u32 = 1;
+/// Alias of the server running on this machine, saved when it starts
+pub const LOCAL: &str = "local";
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Server {
 	pub address: String,
 	pub port: u16,
 	pub password: String,
+	/// Whether the server uses HTTPS
+	#[serde(default)]
+	pub tls: bool,
+	/// SHA-256 fingerprint of the trusted server certificate, empty to verify it with system certificate authorities
+	#[serde(default)]
+	pub fingerprint: String,
+	/// Tags grouping the server with others, any of them can be used as a target instead of the alias
+	#[serde(default)]
+	pub tags: BTreeSet<String>,
+	/// Whether to send the plain password to servers that do not support signed requests
+	#[serde(default)]
+	pub legacy_auth: bool,
 	pub default: bool,
 }
 
+impl Server {
+	pub fn url(&self) -> String {
+		let scheme = if self.tls { "https" } else { "http" };
+		format!("{scheme}://{}:{}", self.address, self.port)
+	}
+
+	/// Returns true if the server address points to this machine
+	pub fn is_local(&self) -> bool {
+		self.address == "localhost"
+			|| self
+				.address
+				.parse::<IpAddr>()
+				.is_ok_and(|ip| ip.is_loopback() || ip.is_unspecified())
+	}
+}
+
 pub type Servers = HashMap<String, Server>;
 
+#[derive(Serialize, Deserialize)]
+struct File {
+	version: u32,
+	#[serde(default)]
+	servers: Servers,
+}
+
 pub fn read() -> Result<Servers> {
 	let path = dirs::racky().join("servers.toml");
 
@@ -22,26 +68,103 @@ pub fn read() -> Result<Servers> {
 		return Ok(HashMap::new());
 	}
 
-	fs::read_to_string(path)
+	let table = fs::read_to_string(path)
 		.map_err(Error::from)
-		.and_then(|s| toml::from_str(&s).map_err(Error::from))
-		.desc("Failed to read servers file")
+		.and_then(|s| toml::from_str::<Table>(&s).map_err(Error::from))
+		.desc("Failed to read servers file")?;
+
+	// Files written by older versions only contain the servers keyed by alias, which may include a `version` server
+	let Some(version) = table.get("version").and_then(|version| version.as_integer()) else {
+		let servers = table.try_into().desc("Failed to parse servers file")?;
+		write(&servers).desc("Failed to migrate servers file")?;
+
+		return Ok(servers);
+	};
+
+	if version > VERSION.into() {
+		bail!("Servers file version {version} is not supported, update Racky to read it");
+	}
+
+	Ok(table.try_into::<File>().desc("Failed to parse servers file")?.servers)
 }
 
 pub fn write(servers: &Servers) -> Result<()> {
-	toml::to_string(&servers)
+	let file = File {
+		version: VERSION,
+		servers: servers.clone(),
+	};
+
+	toml::to_string(&file)
 		.map_err(Error::from)
 		.and_then(|s| fs::write(dirs::racky().join("servers.toml"), s).map_err(Error::from))
 		.desc("Failed to write servers file")
 }
 
-pub fn get(alias: Option<String>) -> Result<Server> {
-	read()?
-		.into_iter()
-		.find(|(a, s)| match &alias {
-			Some(alias) => a == alias,
-			None => s.default,
-		})
-		.map(|(_, s)| s)
-		.context("No matching server found")
+/// Checks that the alias can be used in a target selector
+pub fn validate_alias(alias: &str) -> Result<()> {
+	ensure!(
+		!alias.is_empty()
+			&& alias != "all"
+			&& alias
+				.chars()
+				.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.'),
+		"Alias {} is invalid, it can only contain letters, digits, `-`, `_` and `.` and cannot be `all`",
+		alias.bold()
+	);
+
+	Ok(())
+}
+
+/// Checks that the tag can be told apart from aliases in a target selector
+pub fn validate_tag(tag: &str) -> Result<()> {
+	ensure!(
+		!tag.is_empty() && tag != "all" && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+		"Tag {} is invalid, it can only contain letters, digits, `-` and `_` and cannot be `all`",
+		tag.bold()
+	);
+
+	Ok(())
+}
+
+/// Returns the servers matching the selector, which is an alias, a tag, a comma separated list of them or `all`,
+/// or the default server if there is no selector (aliases take precedence over tags with the same name)
+pub fn resolve(selector: Option<String>) -> Result<Vec<(String, Server)>> {
+	let mut servers = read()?.into_iter().collect::<Vec<_>>();
+	servers.sort_by(|a, b| a.0.cmp(&b.0));
+
+	let matching = match selector.as_deref().map(str::trim) {
+		None => servers.into_iter().filter(|(_, server)| server.default).collect(),
+		Some("all") => servers,
+		Some(selector) => {
+			let mut matching = Vec::<(String, Server)>::new();
+
+			for name in selector.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+				let found = match servers.iter().find(|(alias, _)| alias == name) {
+					Some(server) => vec![server],
+					None => servers
+						.iter()
+						.filter(|(_, server)| server.tags.contains(name))
+						.collect(),
+				};
+
+				ensure!(
+					!found.is_empty(),
+					"Server with alias or tag {} does not exist",
+					name.bold()
+				);
+
+				for (alias, server) in found {
+					if !matching.iter().any(|(matched, _)| matched == alias) {
+						matching.push((alias.clone(), server.clone()));
+					}
+				}
+			}
+
+			matching
+		}
+	};
+
+	ensure!(!matching.is_empty(), "No matching server found");
+
+	Ok(matching)
 }
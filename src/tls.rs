@@ -0,0 +1,203 @@
+use std::{
+	fs,
+	path::{Path, PathBuf},
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+
+use anyhow::{Context, Result};
+use aws_lc_rs::digest::{self, SHA256};
+use jiff::{Timestamp, tz::TimeZone};
+use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, PKCS_ECDSA_P256_SHA256};
+use rustls::{
+	ClientConfig, DigitallySignedStruct, ServerConfig, SignatureScheme,
+	client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+	crypto::{self, CryptoProvider},
+	pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime, pem::PemObject},
+};
+use sysinfo::System;
+
+use crate::{dirs, ext::ResultExt, util};
+
+/// How long generated self-signed certificates are valid for
+const CERTIFICATE_VALIDITY: Duration = Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+/// Returns the SHA-256 fingerprint of a DER encoded certificate as colon separated hex pairs
+pub fn fingerprint(certificate: &[u8]) -> String {
+	digest::digest(&SHA256, certificate)
+		.as_ref()
+		.iter()
+		.map(|byte| format!("{byte:02X}"))
+		.collect::<Vec<_>>()
+		.join(":")
+}
+
+/// Returns true if both fingerprints match, ignoring case and separators
+pub fn fingerprints_match(a: &str, b: &str) -> bool {
+	let normalize = |fingerprint: &str| {
+		fingerprint
+			.chars()
+			.filter(char::is_ascii_hexdigit)
+			.collect::<String>()
+			.to_ascii_lowercase()
+	};
+
+	normalize(a) == normalize(b)
+}
+
+/// Returns the certificate and key paths from the config or the default ones in the Racky directory
+pub fn get_paths(cert: &str, key: &str) -> (PathBuf, PathBuf) {
+	let resolve = |path: &str, default: &str| {
+		if path.is_empty() {
+			dirs::racky().join("tls").join(default)
+		} else {
+			Path::new(path).to_owned()
+		}
+	};
+
+	(resolve(cert, "cert.pem"), resolve(key, "key.pem"))
+}
+
+/// Creates a self-signed ECDSA P-256 certificate for this host and writes it with its key as PEM files
+pub fn generate(cert_path: &Path, key_path: &Path) -> Result<()> {
+	let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256).desc("Failed to generate private key")?;
+
+	let mut names = vec![String::from("localhost"), String::from("127.0.0.1")];
+	names.extend(System::host_name().filter(|name| name != "localhost"));
+
+	let mut params = CertificateParams::new(names).desc("Failed to create certificate parameters")?;
+	let mut name = DistinguishedName::new();
+	name.push(DnType::CommonName, "Racky");
+	params.distinguished_name = name;
+
+	let date = |time: Timestamp| {
+		let date = time.to_zoned(TimeZone::UTC).date();
+		rcgen::date_time_ymd(date.year().into(), date.month() as u8, date.day() as u8)
+	};
+
+	let now = Timestamp::now();
+	params.not_before = date(now - Duration::from_secs(24 * 60 * 60));
+	params.not_after = date(now + CERTIFICATE_VALIDITY);
+
+	let certificate = params.self_signed(&key_pair).desc("Failed to sign certificate")?;
+
+	for path in [cert_path, key_path] {
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent).with_desc(|| format!("Failed to create {} directory", parent.display()))?;
+		}
+	}
+
+	fs::write(cert_path, certificate.pem()).desc("Failed to write certificate")?;
+	util::write_private(key_path, key_pair.serialize_pem()).desc("Failed to write private key")?;
+
+	Ok(())
+}
+
+/// Loads the certificate chain and key, returns the server config with the fingerprint of the certificate
+pub fn server_config(cert_path: &Path, key_path: &Path) -> Result<(Arc<ServerConfig>, String)> {
+	let certificates = CertificateDer::pem_slice_iter(&fs::read(cert_path).desc("Failed to read certificate")?)
+		.collect::<Result<Vec<_>, _>>()
+		.desc("Failed to parse certificate")?;
+
+	let key = PrivateKeyDer::from_pem_slice(&fs::read(key_path).desc("Failed to read private key")?)
+		.desc("Failed to parse private key")?;
+
+	let fingerprint = fingerprint(certificates.first().context("Certificate file is empty")?);
+
+	let config = ServerConfig::builder_with_provider(provider())
+		.with_safe_default_protocol_versions()?
+		.with_no_client_auth()
+		.with_single_cert(certificates, key)
+		.desc("Failed to create TLS config")?;
+
+	Ok((Arc::new(config), fingerprint))
+}
+
+/// Creates a client config that only trusts the certificate with the given fingerprint
+pub fn pinned_config(fingerprint: &str) -> Result<ClientConfig> {
+	client_config(Verifier {
+		provider: provider(),
+		pinned: Some(fingerprint.to_owned()),
+		seen: Arc::default(),
+	})
+}
+
+/// Creates a client config that trusts any certificate and stores the fingerprint of the last one it saw
+pub fn capturing_config() -> Result<(ClientConfig, Arc<Mutex<Option<String>>>)> {
+	let seen = Arc::default();
+	let config = client_config(Verifier {
+		provider: provider(),
+		pinned: None,
+		seen: Arc::clone(&seen),
+	})?;
+
+	Ok((config, seen))
+}
+
+fn client_config(verifier: Verifier) -> Result<ClientConfig> {
+	Ok(ClientConfig::builder_with_provider(provider())
+		.with_safe_default_protocol_versions()?
+		.dangerous()
+		.with_custom_certificate_verifier(Arc::new(verifier))
+		.with_no_client_auth())
+}
+
+fn provider() -> Arc<CryptoProvider> {
+	Arc::new(crypto::aws_lc_rs::default_provider())
+}
+
+/// Verifies servers by certificate fingerprint instead of a certificate authority,
+/// handshake signatures are still checked so the server must own the certificate key
+#[derive(Debug)]
+struct Verifier {
+	provider: Arc<CryptoProvider>,
+	pinned: Option<String>,
+	seen: Arc<Mutex<Option<String>>>,
+}
+
+impl ServerCertVerifier for Verifier {
+	fn verify_server_cert(
+		&self,
+		end_entity: &CertificateDer<'_>,
+		_intermediates: &[CertificateDer<'_>],
+		_server_name: &ServerName<'_>,
+		_ocsp_response: &[u8],
+		_now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		let fingerprint = fingerprint(end_entity);
+
+		if let Some(pinned) = &self.pinned
+			&& !fingerprints_match(pinned, &fingerprint)
+		{
+			return Err(rustls::Error::General(format!(
+				"Server certificate fingerprint {fingerprint} does not match the pinned one"
+			)));
+		}
+
+		*self.seen.lock().unwrap() = Some(fingerprint);
+
+		Ok(ServerCertVerified::assertion())
+	}
+
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.provider.signature_verification_algorithms.supported_schemes()
+	}
+}
@@ -0,0 +1,197 @@
+use std::{
+	collections::BTreeMap,
+	fmt::{self, Display, Formatter},
+	fs,
+};
+
+use anyhow::{Error, Result};
+use aws_lc_rs::{
+	constant_time,
+	digest::{self, SHA256},
+	rand::{SecureRandom, SystemRandom},
+};
+use clap::ValueEnum;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	auth::{self, from_hex, to_hex},
+	dirs,
+	ext::ResultExt,
+	util,
+};
+
+/// Prefix of API token values, which look like `racky_<id>_<secret>`
+const PREFIX: &str = "racky_";
+
+/// Access granted to an API token, each scope also grants everything the previous ones do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+	/// Server and program status, program list, logs and events
+	Read,
+	/// Start, stop and restart programs and change their config
+	Operator,
+	/// Add, update and remove programs
+	Deploy,
+	/// Reboot, shut down, update and configure the server and manage API tokens
+	Admin,
+}
+
+impl Display for Scope {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Read => write!(f, "read"),
+			Self::Operator => write!(f, "operator"),
+			Self::Deploy => write!(f, "deploy"),
+			Self::Admin => write!(f, "admin"),
+		}
+	}
+}
+
+/// API token issued by the server, only a verifier of its value is stored
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+	pub id: String,
+	pub scope: Scope,
+	pub created: Timestamp,
+	/// Hash of the client key derived from the token value, which cannot sign requests itself
+	verifier: String,
+}
+
+impl Token {
+	/// Creates a token with the given scope, returns it together with its value
+	pub fn generate(scope: Scope) -> (Self, String) {
+		let mut bytes = [0; 40];
+		SystemRandom::new().fill(&mut bytes).expect("Failed to generate token");
+
+		let id = to_hex(&bytes[..8]);
+		let value = format!("{PREFIX}{id}_{}", to_hex(&bytes[8..]));
+
+		let token = Self {
+			id,
+			scope,
+			created: Timestamp::now(),
+			verifier: to_hex(&auth::stored_key(&client_key(&value))),
+		};
+
+		(token, value)
+	}
+
+	pub fn verify(&self, value: &str) -> bool {
+		constant_time::verify_slices_are_equal(&auth::stored_key(&client_key(value)), &self.stored_key()).is_ok()
+	}
+
+	/// Returns the key signatures made with this token are verified with
+	pub fn stored_key(&self) -> Vec<u8> {
+		from_hex(&self.verifier).unwrap_or_default()
+	}
+}
+
+/// Returns the ID of the given token value, none if the value is not a token
+pub fn get_id(value: &str) -> Option<&str> {
+	value.strip_prefix(PREFIX)?.split_once('_').map(|(id, _)| id)
+}
+
+/// Returns the client key requests are signed with using the given token value
+pub fn client_key(value: &str) -> Vec<u8> {
+	digest::digest(&SHA256, value.as_bytes()).as_ref().to_vec()
+}
+
+/// API tokens by name
+pub type Tokens = BTreeMap<String, Token>;
+
+pub fn read() -> Result<Tokens> {
+	let path = dirs::racky().join("tokens.toml");
+
+	if !path.exists() {
+		return Ok(BTreeMap::new());
+	}
+
+	fs::read_to_string(path)
+		.map_err(Error::from)
+		.and_then(|s| toml::from_str(&s).map_err(Error::from))
+		.desc("Failed to read tokens file")
+}
+
+pub fn write(tokens: &Tokens) -> Result<()> {
+	let path = dirs::racky().join("tokens.toml");
+
+	toml::to_string(&tokens)
+		.map_err(Error::from)
+		.and_then(|s| util::write_private(&path, s).map_err(Error::from))
+		.desc("Failed to write tokens file")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::auth::{Signature, SignedRequest};
+
+	const REQUEST: SignedRequest = SignedRequest {
+		method: "POST",
+		path: "/api/programs/web/restart",
+		content_type: "",
+		body: b"",
+	};
+
+	#[test]
+	fn generated_token_verifies_its_value() {
+		let (token, value) = Token::generate(Scope::Deploy);
+
+		assert!(value.starts_with(PREFIX));
+		assert_eq!(get_id(&value), Some(token.id.as_str()));
+		assert!(token.verify(&value));
+		assert!(!token.verify(&value[..value.len() - 1]));
+		assert!(!token.verify(""));
+	}
+
+	#[test]
+	fn value_is_not_stored() {
+		let (token, value) = Token::generate(Scope::Read);
+		let serialized = toml::to_string(&token).unwrap();
+
+		assert!(!serialized.contains(&value[PREFIX.len() + token.id.len() + 1..]));
+		assert!(!serialized.contains(&to_hex(&client_key(&value))));
+	}
+
+	#[test]
+	fn get_id_of_values() {
+		assert_eq!(get_id("racky_0123_abcd"), Some("0123"));
+		assert_eq!(get_id("racky_0123"), None);
+		assert_eq!(get_id("secret"), None);
+	}
+
+	#[test]
+	fn signed_with_token() {
+		let (token, value) = Token::generate(Scope::Operator);
+		let signature = Signature::new(&client_key(&value), Some(&token.id), &REQUEST);
+
+		assert!(signature.verify(&token.stored_key(), &REQUEST));
+
+		let (other, value) = Token::generate(Scope::Operator);
+		let signature = Signature::new(&client_key(&value), Some(&other.id), &REQUEST);
+		assert!(!signature.verify(&token.stored_key(), &REQUEST));
+	}
+
+	#[test]
+	fn verifier_cannot_sign() {
+		let (token, _) = Token::generate(Scope::Admin);
+		let signature = Signature::new(&token.stored_key(), Some(&token.id), &REQUEST);
+
+		assert!(!signature.verify(&token.stored_key(), &REQUEST));
+	}
+
+	#[test]
+	fn scopes_grant_previous_ones() {
+		assert!(Scope::Read < Scope::Operator);
+		assert!(Scope::Operator < Scope::Deploy);
+		assert!(Scope::Deploy < Scope::Admin);
+		assert_eq!(Scope::Admin.to_string(), "admin");
+		assert!(
+			toml::to_string(&Token::generate(Scope::Deploy).0)
+				.unwrap()
+				.contains("scope = \"deploy\"")
+		);
+	}
+}
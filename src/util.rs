@@ -1,7 +1,13 @@
 #[cfg(unix)]
-use std::os::unix::process::ExitStatusExt;
+use std::os::unix::{
+	fs::{OpenOptionsExt, PermissionsExt},
+	process::ExitStatusExt,
+};
 use std::{
 	env,
+	fs::{self, OpenOptions},
+	io::{self, Write},
+	path::Path,
 	process::ExitStatus,
 	thread,
 	time::{Duration, SystemTime},
@@ -84,6 +90,23 @@ pub fn get_exit_code(status: &ExitStatus) -> i32 {
 	code.unwrap_or(-1)
 }
 
+/// Returns the name of the signal that terminated a process, if any
+#[cfg(unix)]
+pub fn get_signal(status: &ExitStatus) -> Option<String> {
+	status
+		.signal()
+		.map(|signal| match nix::sys::signal::Signal::try_from(signal) {
+			Ok(signal) => signal.as_str().to_owned(),
+			Err(_) => signal.to_string(),
+		})
+}
+
+/// Processes are never terminated by signals outside Unix
+#[cfg(not(unix))]
+pub fn get_signal(_status: &ExitStatus) -> Option<String> {
+	None
+}
+
 /// Returns the service name for the current user
 pub fn get_service() -> String {
 	get_user()
@@ -96,6 +119,23 @@ pub fn is_service() -> bool {
 	cfg!(target_os = "linux") && env::var("INVOCATION_ID").is_ok()
 }
 
+/// Writes a file that only the current user can access, it is never readable by others even while being written
+pub fn write_private(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+	let mut options = OpenOptions::new();
+	options.write(true).create(true).truncate(true);
+
+	#[cfg(unix)]
+	options.mode(0o600);
+
+	let mut file = options.open(path)?;
+
+	// The mode only applies to new files, existing ones are restricted once truncated
+	#[cfg(unix)]
+	file.set_permissions(fs::Permissions::from_mode(0o600))?;
+
+	file.write_all(contents.as_ref())
+}
+
 /// Delays the execution of a function for a given number of seconds
 pub fn delay<F: FnOnce() + Send + 'static>(seconds: u64, f: F) {
 	thread::spawn(move || {
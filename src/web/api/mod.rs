@@ -0,0 +1,118 @@
+use std::fmt::Display;
+
+use axum::{
+	Json, Router,
+	http::StatusCode,
+	middleware::from_fn_with_state,
+	response::{IntoResponse, Response},
+	routing::{get, patch},
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{core::CorePtr, tokens::Scope, web::middleware::auth::require};
+
+mod program;
+mod server;
+
+pub type Result<T> = std::result::Result<Json<T>, Error>;
+
+/// Routes of the versioned JSON API, nested under `/api/v1`
+pub fn router() -> Router<CorePtr> {
+	Router::new()
+		.route("/programs", get(program::list))
+		.route("/programs/{program}", get(program::status))
+		.route(
+			"/programs/{program}/config",
+			get(program::config)
+				.merge(patch(program::update_config).route_layer(from_fn_with_state(Scope::Operator, require))),
+		)
+		.route("/programs/{program}/logs", get(program::logs))
+		.route("/server/status", get(server::status))
+		.route(
+			"/server/config",
+			get(server::config)
+				.merge(patch(server::update_config).route_layer(from_fn_with_state(Scope::Admin, require))),
+		)
+		.fallback(|| async { Error::not_found("Route does not exist") })
+}
+
+/// Error returned by API routes as a JSON body
+#[derive(Debug, Serialize)]
+pub struct Error {
+	#[serde(skip)]
+	status: StatusCode,
+	code: &'static str,
+	message: String,
+}
+
+impl Error {
+	pub fn new(status: StatusCode, code: &'static str, message: impl Display) -> Self {
+		Self {
+			status,
+			code,
+			message: message.to_string(),
+		}
+	}
+
+	pub fn not_found(message: impl Display) -> Self {
+		Self::new(StatusCode::NOT_FOUND, "not_found", message)
+	}
+
+	pub fn bad_request(message: impl Display) -> Self {
+		Self::new(StatusCode::BAD_REQUEST, "bad_request", message)
+	}
+
+	pub fn unauthorized() -> Self {
+		Self::new(
+			StatusCode::UNAUTHORIZED,
+			"unauthorized",
+			"Invalid or missing credentials",
+		)
+	}
+
+	pub fn forbidden(scope: impl Display) -> Self {
+		Self::new(
+			StatusCode::FORBIDDEN,
+			"forbidden",
+			format!("This request requires the {scope} scope"),
+		)
+	}
+
+	pub fn internal(message: impl Display) -> Self {
+		Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal_error", message)
+	}
+}
+
+impl IntoResponse for Error {
+	fn into_response(self) -> Response {
+		#[derive(Serialize)]
+		struct Body<'a> {
+			error: &'a Error,
+		}
+
+		(self.status, Json(Body { error: &self })).into_response()
+	}
+}
+
+/// Setting of a configuration with its current and default value
+#[derive(Debug, Serialize)]
+pub struct Setting {
+	pub name: String,
+	pub value: Value,
+	pub default: Value,
+	pub description: String,
+}
+
+/// Converts a JSON value to the text representation used by configuration setters
+fn to_setting_value(value: &Value) -> String {
+	match value {
+		Value::Null => String::new(),
+		Value::String(value) => value.to_owned(),
+		Value::Array(values) => shell_words::join(values.iter().map(|value| match value {
+			Value::String(value) => value.to_owned(),
+			value => value.to_string(),
+		})),
+		value => value.to_string(),
+	}
+}
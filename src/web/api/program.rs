@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+	Json,
+	extract::{Path, Query, State, rejection::JsonRejection},
+};
+use documented::DocumentedFields;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+	core::{
+		CorePtr,
+		program::{self, Config, Program, ProgramPtr, Run},
+	},
+	logger,
+	web::{
+		api::{self, Error, Setting},
+		program::{config::track_scheduled, list},
+	},
+};
+
+#[derive(Debug, Serialize)]
+pub struct Status {
+	/// One of `idle`, `running`, `unhealthy`, `restarting`, `stopped`, `finished`, `errored` or `failed`
+	pub state: &'static str,
+	pub pid: Option<u32>,
+	pub message: Option<String>,
+}
+
+impl From<&program::Status> for Status {
+	fn from(status: &program::Status) -> Self {
+		let (state, message) = match status {
+			program::Status::Idle => ("idle", None),
+			program::Status::Running(_) => ("running", None),
+			program::Status::Unhealthy(_) => ("unhealthy", None),
+			program::Status::Restarting => ("restarting", None),
+			program::Status::Stopped => ("stopped", None),
+			program::Status::Finished(output) => ("finished", Some(output.to_owned())),
+			program::Status::Errored(output) => ("errored", Some(output.to_owned())),
+			program::Status::Failed(output) => ("failed", Some(output.to_owned())),
+		};
+
+		Self {
+			state,
+			pid: status.pid(),
+			message,
+		}
+	}
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+	pub name: String,
+	pub status: Status,
+	pub executions: usize,
+	/// Runtime of the current or last run in seconds
+	pub runtime: u64,
+	pub start_time: Option<Timestamp>,
+	pub next_run: Option<Timestamp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Details {
+	pub name: String,
+	pub status: Status,
+	pub executions: usize,
+	pub last_exit_code: Option<i32>,
+	pub restart_policy: String,
+	pub args: Vec<String>,
+	pub dependencies: Vec<Dependency>,
+	pub schedule: Option<String>,
+	pub next_run: Option<Timestamp>,
+	pub health: Option<Health>,
+	pub current: Period,
+	pub total: Period,
+	pub history: Vec<Run>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dependency {
+	pub name: String,
+	pub status: Status,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Health {
+	pub check: String,
+	pub healthy: bool,
+	pub failures: usize,
+	pub error: Option<String>,
+	pub checked: Option<Timestamp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Period {
+	pub restart_attempts: usize,
+	/// Runtime in seconds
+	pub runtime: u64,
+	pub start_time: Option<Timestamp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Configuration {
+	pub settings: Vec<Setting>,
+	pub variables: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Logs {
+	pub program: String,
+	pub page: usize,
+	pub lines: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+	page: Option<usize>,
+}
+
+pub async fn list(State(core): State<CorePtr>) -> api::Result<Vec<Summary>> {
+	let programs = list::collect(&core).map_err(Error::internal)?;
+
+	Ok(Json(
+		programs
+			.into_iter()
+			.map(|(name, program)| {
+				let state = program.state();
+
+				Summary {
+					name,
+					status: Status::from(&state.status),
+					executions: state.executions,
+					runtime: state.get_runtime().current.as_secs(),
+					start_time: state.start_time.current.and_then(|time| Timestamp::try_from(time).ok()),
+					next_run: state.next_run.and_then(|time| Timestamp::try_from(time).ok()),
+				}
+			})
+			.collect(),
+	))
+}
+
+pub async fn status(State(core): State<CorePtr>, Path(name): Path<String>) -> api::Result<Details> {
+	let program = get_program(&core, &name)?;
+	let config = program.config();
+	let state = program.state();
+	let runtime = state.get_runtime();
+	let timestamp = |time: Option<std::time::SystemTime>| time.and_then(|time| Timestamp::try_from(time).ok());
+
+	Ok(Json(Details {
+		name,
+		status: Status::from(&state.status),
+		executions: state.executions,
+		last_exit_code: state.exit_code,
+		restart_policy: config.restart_policy.to_string(),
+		args: config.args.to_vec(),
+		dependencies: config
+			.depends_on
+			.iter()
+			.map(|name| Dependency {
+				name: name.to_owned(),
+				status: Status::from(
+					&core
+						.get_program(name)
+						.map(|dependency| dependency.status())
+						.unwrap_or_default(),
+				),
+			})
+			.collect(),
+		schedule: (!config.schedule.is_none()).then(|| config.schedule.to_string()),
+		next_run: timestamp(state.next_run),
+		health: (!config.health_check.is_none()).then(|| Health {
+			check: config.health_check.to_string(),
+			healthy: state.health.is_healthy(),
+			failures: state.health.failures,
+			error: state.health.error.clone(),
+			checked: timestamp(state.health.checked),
+		}),
+		current: Period {
+			restart_attempts: state.attempts.current,
+			runtime: runtime.current.as_secs(),
+			start_time: timestamp(state.start_time.current),
+		},
+		total: Period {
+			restart_attempts: state.attempts.total,
+			runtime: runtime.total.as_secs(),
+			start_time: timestamp(state.start_time.total),
+		},
+		history: state.history.iter().cloned().collect(),
+	}))
+}
+
+pub async fn config(State(core): State<CorePtr>, Path(name): Path<String>) -> api::Result<Configuration> {
+	let program = get_program(&core, &name)?;
+	program.load_config();
+
+	collect_config(&program).map(Json)
+}
+
+/// Updates the given settings, `null` restores the default value of a setting or removes a variable,
+/// while empty strings and arrays clear it. Nothing is changed unless all values are valid
+pub async fn update_config(
+	State(core): State<CorePtr>,
+	Path(name): Path<String>,
+	body: Result<Json<HashMap<String, Value>>, JsonRejection>,
+) -> api::Result<Configuration> {
+	let Json(values) = body.map_err(|err| Error::bad_request(err.body_text()))?;
+	let program = get_program(&core, &name)?;
+	program.load_config();
+
+	let defaults = Config::default();
+	let mut validated = program.config();
+	let mut updates = Vec::with_capacity(values.len());
+
+	for (key, value) in &values {
+		let value = match value {
+			Value::Null => defaults.get(key).map(|default| default.to_string()).unwrap_or_default(),
+			value => api::to_setting_value(value),
+		};
+
+		let (setting, setting_value) = match Config::migrate(key, &value) {
+			Some((setting, value)) => (setting, value),
+			None => (key.as_str(), value.clone()),
+		};
+
+		// Anything that is not a setting is a variable, which accepts any value
+		if validated.get(setting).is_some() {
+			validated.set(setting, &setting_value).map_err(|err| {
+				Error::new(
+					axum::http::StatusCode::BAD_REQUEST,
+					"invalid_setting",
+					format!("Failed to set `{key}` to `{value}`: {err}"),
+				)
+			})?;
+		}
+
+		updates.push((key, value));
+	}
+
+	for (key, value) in updates {
+		program.update_config(key, &value).map_err(Error::internal)?;
+	}
+
+	program.save_config().map_err(Error::internal)?;
+	track_scheduled(&core, &program);
+
+	collect_config(&program).map(Json)
+}
+
+pub async fn logs(
+	State(core): State<CorePtr>,
+	Path(name): Path<String>,
+	Query(query): Query<LogsQuery>,
+) -> api::Result<Logs> {
+	let path = get_program(&core, &name)?.paths().logs.clone();
+	let page = query.page.unwrap_or_default();
+
+	if !path.exists() {
+		return Err(Error::not_found(format!("Program {name} has no logs")));
+	}
+
+	let logs = task::spawn_blocking(move || logger::read_file(&path, page))
+		.await
+		.map_err(Error::internal)?
+		.map_err(Error::not_found)?;
+
+	Ok(Json(Logs {
+		program: name,
+		page,
+		lines: logs.lines().map(str::to_owned).collect(),
+	}))
+}
+
+/// Returns the loaded program or a fresh instance if it is installed but has not been loaded yet
+fn get_program(core: &CorePtr, name: &str) -> Result<ProgramPtr, Error> {
+	core.get_program(name)
+		.or_else(|| {
+			Some(Program::new(name)).filter(|program| Program::is_valid_name(name) && program.paths().validate())
+		})
+		.ok_or_else(|| Error::not_found(format!("Program {name} does not exist")))
+}
+
+fn collect_config(program: &ProgramPtr) -> Result<Configuration, Error> {
+	let defaults = Config::default();
+	let current = serde_json::to_value(program.config()).map_err(Error::internal)?;
+	let default_values = serde_json::to_value(&defaults).map_err(Error::internal)?;
+
+	let settings = (&defaults)
+		.into_iter()
+		.map(|(name, _)| Setting {
+			name: name.to_owned(),
+			value: current.get(name).cloned().unwrap_or_default(),
+			default: default_values.get(name).cloned().unwrap_or_default(),
+			description: Config::get_field_docs(name).unwrap_or_default().trim().to_owned(),
+		})
+		.collect();
+
+	Ok(Configuration {
+		settings,
+		variables: program.state().vars.clone().into_iter().collect(),
+	})
+}
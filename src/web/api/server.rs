@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use axum::{
+	Json,
+	extract::{State, rejection::JsonRejection},
+	http::StatusCode,
+};
+use documented::DocumentedFields;
+use serde_json::Value;
+use tokio::task;
+
+use crate::{
+	config::{Config, HASHED},
+	core::CorePtr,
+	web::{
+		api::{self, Error, Setting},
+		server::status,
+	},
+};
+
+pub async fn status(State(core): State<CorePtr>) -> api::Result<status::Status> {
+	task::spawn_blocking(move || status::collect(&core))
+		.await
+		.map(Json)
+		.map_err(Error::internal)
+}
+
+pub async fn config() -> api::Result<Vec<Setting>> {
+	collect_config(&Config::new()).map(Json)
+}
+
+/// Updates the given settings, `null` restores the default value of a setting.
+/// Nothing is changed unless all values are valid and the config is saved
+pub async fn update_config(body: Result<Json<HashMap<String, Value>>, JsonRejection>) -> api::Result<Vec<Setting>> {
+	let Json(values) = body.map_err(|err| Error::bad_request(err.body_text()))?;
+	let defaults = Config::default();
+	let mut current = Config::new_mut();
+	let mut config = current.clone();
+
+	for (key, value) in &values {
+		let default = defaults.get(key).ok_or_else(|| {
+			Error::new(
+				StatusCode::BAD_REQUEST,
+				"invalid_setting",
+				format!("Setting `{key}` does not exist"),
+			)
+		})?;
+
+		let value = match value {
+			Value::Null => default.to_string(),
+			value => api::to_setting_value(value),
+		};
+
+		config
+			.update(key, &value)
+			.map_err(|err| Error::new(StatusCode::BAD_REQUEST, "invalid_setting", err))?;
+	}
+
+	config.save().map_err(Error::internal)?;
+	*current = config;
+
+	collect_config(&current).map(Json)
+}
+
+fn collect_config(config: &Config) -> Result<Vec<Setting>, Error> {
+	let defaults = Config::default();
+	let current = serde_json::to_value(config).map_err(Error::internal)?;
+	let default_values = serde_json::to_value(&defaults).map_err(Error::internal)?;
+
+	Ok((&defaults)
+		.into_iter()
+		.map(|(name, _)| {
+			let mut value = current.get(name).cloned().unwrap_or_default();
+
+			// Hashes of secrets are never returned
+			if HASHED.contains(&name) && value.as_str().is_some_and(|value| !value.is_empty()) {
+				value = Value::String(String::from("********"));
+			}
+
+			Setting {
+				name: name.to_owned(),
+				value,
+				default: default_values.get(name).cloned().unwrap_or_default(),
+				description: Config::get_field_docs(name).unwrap_or_default().trim().to_owned(),
+			}
+		})
+		.collect())
+}
@@ -0,0 +1,8 @@
+use axum::{extract::State, response::IntoResponse};
+
+use crate::{response, web::middleware::auth::AuthPtr};
+
+/// Returns the public password hash parameters clients need to sign requests, empty if no password is set
+pub async fn main(State(auth): State<AuthPtr>) -> impl IntoResponse {
+	response!(OK, auth.params().unwrap_or_default())
+}
@@ -0,0 +1,71 @@
+use std::net::SocketAddr;
+
+use axum::{
+	Extension, Form,
+	extract::{ConnectInfo, State},
+	http::{HeaderMap, header},
+	response::{Html, IntoResponse},
+};
+use serde::Deserialize;
+use tokio::time;
+
+use crate::{
+	config::Config,
+	response,
+	web::middleware::auth::{self, AuthPtr, SESSION_COOKIE, SESSION_DURATION},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+	password: String,
+}
+
+pub async fn main() -> impl IntoResponse {
+	Html(include_str!("index.html"))
+}
+
+pub async fn login(
+	State(auth): State<AuthPtr>,
+	connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+	Form(request): Form<LoginRequest>,
+) -> impl IntoResponse {
+	let ip = connect_info.map(|Extension(ConnectInfo(address))| address.ip());
+
+	if let Some(remaining) = ip.and_then(|ip| auth.lockout(ip)) {
+		return auth::locked_out(remaining, false);
+	}
+
+	if !auth.check_password(&request.password) {
+		if let Some(ip) = ip {
+			time::sleep(auth.fail(ip)).await;
+		}
+
+		return response!(UNAUTHORIZED, "Invalid password");
+	}
+
+	if let Some(ip) = ip {
+		auth.succeed(ip);
+	}
+
+	let cookie = session_cookie(&auth.create_session(), SESSION_DURATION.as_secs());
+
+	auth::with_identity(
+		([(header::SET_COOKIE, cookie)], "Logged in successfully").into_response(),
+		auth::Identity::Password,
+	)
+}
+
+pub async fn logout(State(auth): State<AuthPtr>, headers: HeaderMap) -> impl IntoResponse {
+	auth.remove_session(&headers);
+
+	let cookie = session_cookie("", 0);
+
+	([(header::SET_COOKIE, cookie)], "Logged out successfully").into_response()
+}
+
+/// Returns the session cookie header, marked `Secure` when the server serves HTTPS
+fn session_cookie(value: &str, max_age: u64) -> String {
+	let secure = if Config::new().tls { "; Secure" } else { "" };
+
+	format!("{SESSION_COOKIE}={value}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Strict{secure}")
+}
@@ -0,0 +1,104 @@
+#[cfg(unix)]
+use std::{
+	fs,
+	io::{Error, ErrorKind},
+	os::unix::fs::{DirBuilderExt, PermissionsExt},
+	path::Path,
+};
+use std::{future, io::Result, net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::serve::Listener;
+use log::trace;
+use rustls::ServerConfig;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::{
+	net::{TcpListener, TcpStream},
+	sync::mpsc::{self, Receiver},
+	time,
+};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+
+/// Maximum time a client has to complete the TLS handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listener that accepts TCP connections and completes their TLS handshakes in separate tasks,
+/// so slow clients cannot block new connections
+pub struct TlsListener {
+	rx: Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+	local_addr: SocketAddr,
+}
+
+impl TlsListener {
+	pub async fn bind(address: &str, port: u16, config: Arc<ServerConfig>) -> Result<Self> {
+		let listener = TcpListener::bind((address, port)).await?;
+		let local_addr = listener.local_addr()?;
+		let acceptor = TlsAcceptor::from(config);
+		let (tx, rx) = mpsc::channel(100);
+
+		tokio::spawn(async move {
+			while !tx.is_closed() {
+				let (stream, addr) = match listener.accept().await {
+					Ok(connection) => connection,
+					Err(err) => {
+						trace!("Failed to accept connection: {err}");
+						time::sleep(Duration::from_millis(100)).await;
+						continue;
+					}
+				};
+
+				let acceptor = acceptor.clone();
+				let tx = tx.clone();
+
+				tokio::spawn(async move {
+					match time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+						Ok(Ok(stream)) => {
+							tx.send((stream, addr)).await.ok();
+						}
+						Ok(Err(err)) => trace!("TLS handshake with {addr} failed: {err}"),
+						Err(_) => trace!("TLS handshake with {addr} timed out"),
+					}
+				});
+			}
+		});
+
+		Ok(Self { rx, local_addr })
+	}
+}
+
+impl Listener for TlsListener {
+	type Io = TlsStream<TcpStream>;
+	type Addr = SocketAddr;
+
+	async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+		match self.rx.recv().await {
+			Some(connection) => connection,
+			// The accepting task only stops when this listener is dropped
+			None => future::pending().await,
+		}
+	}
+
+	fn local_addr(&self) -> Result<Self::Addr> {
+		Ok(self.local_addr)
+	}
+}
+
+/// Binds a Unix socket that only the current user can connect to, replacing a stale one left by a previous server
+#[cfg(unix)]
+pub fn bind_socket(path: &Path) -> Result<UnixListener> {
+	// Access is restricted by the directory rather than the socket file, so there is no window where others can connect
+	if let Some(dir) = path.parent() {
+		fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+		fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+	}
+
+	if path.exists() {
+		if std::os::unix::net::UnixStream::connect(path).is_ok() {
+			return Err(Error::new(ErrorKind::AddrInUse, "another server is listening on it"));
+		}
+
+		fs::remove_file(path)?;
+	}
+
+	UnixListener::bind(path)
+}
@@ -0,0 +1,291 @@
+use std::{
+	collections::HashMap,
+	fmt::{Display, Write},
+	sync::Mutex,
+};
+
+use axum::{
+	body::Body,
+	extract::{Request, State},
+	http::header,
+	middleware::Next,
+	response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use sysinfo::{Pid, System};
+use tokio::task;
+
+use crate::{
+	auth::PasswordHash,
+	config::Config,
+	core::CorePtr,
+	response,
+	web::{
+		middleware::auth::{self, AuthPtr},
+		server::status::{self, Status},
+	},
+};
+
+lazy_static! {
+	// Kept between scrapes so CPU usage is measured over the scrape interval
+	static ref SYSTEM: Mutex<System> = Mutex::new(System::new_all());
+}
+
+/// Lets scrapers in with the metrics token as a bearer token, anything else needs the usual authentication
+pub async fn authorize(State(auth): State<AuthPtr>, request: Request<Body>, next: Next) -> Response {
+	let hash = Config::new().metrics_token.parse::<PasswordHash>().ok();
+
+	let is_scraper = hash.is_some_and(|hash| {
+		request
+			.headers()
+			.get(header::AUTHORIZATION)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.strip_prefix("Bearer "))
+			.is_some_and(|token| hash.verify(token))
+	});
+
+	if is_scraper {
+		return next.run(request).await;
+	}
+
+	auth::main(State(auth), request, next).await.into_response()
+}
+
+pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
+	match task::spawn_blocking(move || collect(&core)).await {
+		Ok(metrics) => (
+			[(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+			metrics,
+		)
+			.into_response(),
+		Err(err) => response!(INTERNAL_SERVER_ERROR, "Failed to collect metrics: {err}"),
+	}
+}
+
+fn collect(core: &CorePtr) -> String {
+	let mut system = SYSTEM.lock().unwrap();
+	system.refresh_all();
+
+	let Status { server, system: host } = status::collect_with(core, &system);
+
+	let mut children = HashMap::<Pid, Vec<Pid>>::new();
+
+	for (pid, process) in system.processes() {
+		if process.thread_kind().is_none()
+			&& let Some(parent) = process.parent()
+		{
+			children.entry(parent).or_default().push(*pid);
+		}
+	}
+
+	let mut programs = core
+		.programs()
+		.iter()
+		.map(|(name, program)| (name.to_owned(), program.state().clone()))
+		.collect::<Vec<_>>();
+
+	programs.sort_by(|a, b| a.0.cmp(&b.0));
+
+	let mut metrics = Metrics::default();
+
+	metrics.add(
+		"racky_program_up",
+		"gauge",
+		"Whether the program process is alive",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.status.is_alive() as u8)),
+	);
+	metrics.add(
+		"racky_program_executions_total",
+		"counter",
+		"Number of times the program was started",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.executions)),
+	);
+	metrics.add(
+		"racky_program_restart_attempts",
+		"gauge",
+		"Restart attempts since the program last recovered",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.attempts.current)),
+	);
+	metrics.add(
+		"racky_program_restart_attempts_total",
+		"counter",
+		"Restart attempts since the program was first started",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.attempts.total)),
+	);
+	metrics.add(
+		"racky_program_runtime_seconds",
+		"gauge",
+		"Runtime of the current or last run of the program",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.get_runtime().current.as_secs())),
+	);
+	metrics.add(
+		"racky_program_runtime_seconds_total",
+		"counter",
+		"Total runtime of the program across all runs",
+		programs
+			.iter()
+			.map(|(name, state)| (label("program", name), state.get_runtime().total.as_secs())),
+	);
+
+	// Usage of the program process and all its descendants
+	let usage = programs
+		.iter()
+		.filter_map(|(name, state)| {
+			let mut pending = vec![Pid::from_u32(state.status.pid()?)];
+			let mut cpu = 0.0;
+			let mut memory = 0;
+
+			while let Some(pid) = pending.pop() {
+				if let Some(process) = system.process(pid) {
+					cpu += process.cpu_usage();
+					memory += process.memory();
+				}
+
+				pending.extend(children.get(&pid).into_iter().flatten());
+			}
+
+			Some((name, cpu, memory))
+		})
+		.collect::<Vec<_>>();
+
+	metrics.add(
+		"racky_program_cpu_usage_percent",
+		"gauge",
+		"CPU usage of the running program, 100 equals one fully used core",
+		usage.iter().map(|(name, cpu, _)| (label("program", name), cpu)),
+	);
+	metrics.add(
+		"racky_program_memory_bytes",
+		"gauge",
+		"Resident memory of the running program",
+		usage.iter().map(|(name, _, memory)| (label("program", name), memory)),
+	);
+
+	metrics.add(
+		"racky_server_info",
+		"gauge",
+		"Racky server version",
+		[(label("version", &server.version), 1)],
+	);
+	metrics.add(
+		"racky_server_uptime_seconds",
+		"gauge",
+		"Uptime of the Racky server",
+		[(String::new(), server.uptime)],
+	);
+	metrics.add(
+		"racky_server_programs",
+		"gauge",
+		"Number of programs loaded by the server",
+		[(String::new(), server.programs)],
+	);
+
+	metrics.add(
+		"racky_host_uptime_seconds",
+		"gauge",
+		"Uptime of the host",
+		[(String::new(), host.uptime)],
+	);
+	metrics.add(
+		"racky_host_processes",
+		"gauge",
+		"Number of processes running on the host",
+		[(String::new(), host.processes)],
+	);
+	metrics.add(
+		"racky_host_cpu_usage_percent",
+		"gauge",
+		"Usage of each CPU core",
+		host.cpus.iter().map(|cpu| (label("cpu", &cpu.name), cpu.usage)),
+	);
+	metrics.add(
+		"racky_host_cpu_frequency_hertz",
+		"gauge",
+		"Frequency of each CPU core",
+		host.cpus
+			.iter()
+			.map(|cpu| (label("cpu", &cpu.name), cpu.frequency * 1_000_000)),
+	);
+	metrics.add(
+		"racky_host_memory_used_bytes",
+		"gauge",
+		"Used memory of the host",
+		[(String::new(), host.memory.used)],
+	);
+	metrics.add(
+		"racky_host_memory_total_bytes",
+		"gauge",
+		"Total memory of the host",
+		[(String::new(), host.memory.total)],
+	);
+	metrics.add(
+		"racky_host_swap_used_bytes",
+		"gauge",
+		"Used swap of the host",
+		[(String::new(), host.swap.used)],
+	);
+	metrics.add(
+		"racky_host_swap_total_bytes",
+		"gauge",
+		"Total swap of the host",
+		[(String::new(), host.swap.total)],
+	);
+	metrics.add(
+		"racky_host_disk_used_bytes",
+		"gauge",
+		"Used space of each disk",
+		host.disks
+			.iter()
+			.map(|disk| (label("disk", &disk.name), disk.usage.used)),
+	);
+	metrics.add(
+		"racky_host_disk_total_bytes",
+		"gauge",
+		"Total space of each disk",
+		host.disks
+			.iter()
+			.map(|disk| (label("disk", &disk.name), disk.usage.total)),
+	);
+	metrics.add(
+		"racky_host_temperature_celsius",
+		"gauge",
+		"Temperature of each sensor",
+		host.temperatures.iter().filter_map(|temperature| {
+			temperature
+				.value
+				.map(|value| (label("sensor", &temperature.label), value))
+		}),
+	);
+
+	metrics.0
+}
+
+/// Metrics in the Prometheus text exposition format
+#[derive(Default)]
+struct Metrics(String);
+
+impl Metrics {
+	fn add<V: Display>(&mut self, name: &str, kind: &str, help: &str, samples: impl IntoIterator<Item = (String, V)>) {
+		writeln!(self.0, "# HELP {name} {help}").unwrap();
+		writeln!(self.0, "# TYPE {name} {kind}").unwrap();
+
+		for (labels, value) in samples {
+			writeln!(self.0, "{name}{labels} {value}").unwrap();
+		}
+	}
+}
+
+fn label(name: &str, value: &str) -> String {
+	let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+	format!("{{{name}=\"{value}\"}}")
+}
@@ -0,0 +1,188 @@
+use std::{convert::Infallible, net::SocketAddr};
+
+use axum::{
+	Form,
+	body::{self, Body, Bytes},
+	extract::{ConnectInfo, FromRequest, Query},
+	http::{Method, Request, header, request::Parts},
+	middleware::Next,
+	response::IntoResponse,
+};
+use futures_util::{StreamExt, stream};
+use jiff::Timestamp;
+use log::error;
+use multer::Multipart;
+use serde_json::{Map, Value};
+use tokio::{
+	sync::mpsc::{self, UnboundedReceiver},
+	task,
+};
+
+use crate::{
+	audit::{self, Entry},
+	consts::MAX_BODY_SIZE,
+	response,
+	web::middleware::auth::Identity,
+};
+
+/// Identity recorded for requests that failed authentication or did not need it
+const UNAUTHENTICATED: &str = "unauthenticated";
+
+/// Records every mutating request in the audit log together with the status of its response
+pub async fn main(request: Request<Body>, next: Next) -> impl IntoResponse {
+	if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+		return next.run(request).await;
+	}
+
+	let (parts, body) = request.into_parts();
+	let content_type = get_content_type(&parts).to_owned();
+
+	let mut params = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+		.map(|Query(params)| params)
+		.unwrap_or_default();
+
+	// Uploads are not buffered, their fields are read from a copy of the stream while the route consumes it
+	let (body, fields) = match multer::parse_boundary(&content_type) {
+		Ok(boundary) if content_type.starts_with("multipart/form-data") => {
+			let (tx, rx) = mpsc::unbounded_channel();
+			let body = Body::from_stream(body.into_data_stream().inspect(move |chunk| {
+				if let Ok(chunk) = chunk {
+					tx.send(chunk.clone()).ok();
+				}
+			}));
+
+			(body, Some(task::spawn(read_multipart(rx, boundary))))
+		}
+		_ => {
+			// Other bodies are small, they are buffered to read their parameters and then handed over as is
+			let bytes = match body::to_bytes(body, MAX_BODY_SIZE).await {
+				Ok(bytes) => bytes,
+				Err(err) => return response!(PAYLOAD_TOO_LARGE, "Failed to read request body: {err}"),
+			};
+
+			params.extend(get_params(&content_type, &bytes).await);
+			(Body::from(bytes), None)
+		}
+	};
+
+	let mut entry = Entry {
+		timestamp: Timestamp::now(),
+		ip: parts
+			.extensions
+			.get::<ConnectInfo<SocketAddr>>()
+			.map(|ConnectInfo(address)| address.ip()),
+		user_agent: parts
+			.headers
+			.get(header::USER_AGENT)
+			.and_then(|v| v.to_str().ok())
+			.map(str::to_owned),
+		identity: String::new(),
+		method: parts.method.to_string(),
+		path: parts.uri.path().to_owned(),
+		params: Vec::new(),
+		status: 0,
+	};
+
+	let response = next.run(Request::from_parts(parts, body)).await;
+	entry.status = response.status().as_u16();
+
+	// Authentication runs inside this layer and attaches the identity to the response
+	entry.identity = response
+		.extensions()
+		.get::<Identity>()
+		.map_or_else(|| UNAUTHENTICATED.to_owned(), Identity::to_string);
+
+	// The route has dropped the body by now, which ends the copy of the stream
+	if let Some(fields) = fields {
+		params.extend(fields.await.unwrap_or_default());
+	}
+
+	entry.params = params
+		.into_iter()
+		.map(|(key, value)| {
+			let value = audit::redact(&key, &value);
+			(key, value)
+		})
+		.collect();
+
+	// Written before responding, so requests that stop the server are recorded too
+	match task::spawn_blocking(move || audit::append(&entry)).await {
+		Ok(Ok(())) => (),
+		Ok(Err(err)) => error!("Failed to write audit log: {err}"),
+		Err(err) => error!("Failed to write audit log: {err}"),
+	}
+
+	response
+}
+
+/// Returns form and JSON parameters of the request body
+async fn get_params(content_type: &str, bytes: &Bytes) -> Vec<(String, String)> {
+	if content_type.starts_with("application/x-www-form-urlencoded") {
+		let request = Request::builder()
+			.method(Method::POST)
+			.header(header::CONTENT_TYPE, content_type)
+			.body(Body::from(bytes.clone()))
+			.unwrap_or_default();
+
+		if let Ok(Form(form)) = Form::<Vec<(String, String)>>::from_request(request, &()).await {
+			return form;
+		}
+	} else if content_type.starts_with("application/json")
+		&& let Ok(object) = serde_json::from_slice::<Map<String, Value>>(bytes)
+	{
+		return object
+			.into_iter()
+			.map(|(key, value)| match value {
+				Value::String(value) => (key, value),
+				value => (key, value.to_string()),
+			})
+			.collect();
+	}
+
+	Vec::new()
+}
+
+/// Reads the fields of a multipart body from the chunks the route receives,
+/// file uploads are only counted and replaced by their size
+async fn read_multipart(rx: UnboundedReceiver<Bytes>, boundary: String) -> Vec<(String, String)> {
+	let chunks = stream::unfold(rx, |mut rx| async move {
+		rx.recv().await.map(|chunk| (Ok::<_, Infallible>(chunk), rx))
+	});
+
+	let mut multipart = Multipart::new(chunks, boundary);
+	let mut params = Vec::new();
+
+	while let Ok(Some(mut field)) = multipart.next_field().await {
+		let name = field.name().unwrap_or_default().to_owned();
+		let is_file = field.file_name().is_some() || name == "file";
+
+		let mut size = 0;
+		let mut data = Vec::new();
+
+		loop {
+			match field.chunk().await {
+				Ok(Some(chunk)) if is_file => size += chunk.len(),
+				Ok(Some(chunk)) => data.extend_from_slice(&chunk),
+				Ok(None) => break,
+				// The route stopped reading the body before the end of the field
+				Err(_) => return params,
+			}
+		}
+
+		if is_file {
+			params.push((name, format!("<{size} bytes>")));
+		} else {
+			params.push((name, String::from_utf8_lossy(&data).into_owned()));
+		}
+	}
+
+	params
+}
+
+fn get_content_type(parts: &Parts) -> &str {
+	parts
+		.headers
+		.get(header::CONTENT_TYPE)
+		.and_then(|v| v.to_str().ok())
+		.unwrap_or_default()
+}
@@ -1,23 +1,561 @@
 use axum::{
-	body::Body,
-	extract::State,
-	http::{Request, StatusCode},
+	body::{self, Body, Bytes},
+	extract::{ConnectInfo, OriginalUri, State},
+	http::{HeaderMap, Method, Request, StatusCode, header},
 	middleware::Next,
-	response::IntoResponse,
+	response::{IntoResponse, Response},
 };
+use std::{
+	collections::HashMap,
+	fmt::{self, Display, Formatter},
+	net::{IpAddr, SocketAddr},
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+use tokio::time;
+
+use anyhow::{Result, bail, ensure};
+
+use crate::{
+	auth::{MAX_CLOCK_SKEW, PasswordHash, Signature, SignedRequest},
+	config::Config,
+	consts::MAX_BODY_SIZE,
+	racky_warn, response,
+	tokens::{self, Scope, Token, Tokens},
+	web::api,
+};
+
+/// Name of the cookie that holds the dashboard session token
+pub const SESSION_COOKIE: &str = "racky_session";
+/// How long a dashboard session stays valid after logging in
+pub const SESSION_DURATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+pub type AuthPtr = Arc<Auth>;
+
+/// Server password hash, API tokens, dashboard sessions, nonces of recently signed requests
+/// and failed authentication attempts of each address
+#[derive(Debug)]
+pub struct Auth {
+	password: Option<PasswordHash>,
+	tokens: Mutex<Tokens>,
+	sessions: Mutex<HashMap<String, Instant>>,
+	nonces: Mutex<HashMap<String, Instant>>,
+	failures: Mutex<HashMap<IpAddr, Failures>>,
+}
+
+/// Who made an authenticated request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Identity {
+	/// The server does not have a password
+	Anonymous,
+	Password,
+	/// Dashboard session created with the password
+	Session,
+	/// Connected through the Unix socket, which only the user running the server can access
+	Local,
+	Token {
+		name: String,
+		scope: Scope,
+	},
+}
+
+impl Identity {
+	/// Returns the scope granted to the identity, everything but API tokens has full access
+	pub fn scope(&self) -> Scope {
+		match self {
+			Self::Token { scope, .. } => *scope,
+			_ => Scope::Admin,
+		}
+	}
+}
+
+impl Display for Identity {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Anonymous => write!(f, "anonymous"),
+			Self::Password => write!(f, "password"),
+			Self::Session => write!(f, "session"),
+			Self::Local => write!(f, "local"),
+			Self::Token { name, .. } => write!(f, "token:{name}"),
+		}
+	}
+}
+
+/// Marks requests received on the Unix socket, they are authenticated by its file permissions
+#[derive(Debug, Clone, Copy)]
+pub struct LocalConnection;
+
+/// Failed authentication attempts of a single address
+#[derive(Debug)]
+struct Failures {
+	count: usize,
+	last: Instant,
+	locked_until: Option<Instant>,
+}
+
+impl Auth {
+	pub fn new(password: Option<PasswordHash>, tokens: Tokens) -> AuthPtr {
+		Arc::new(Self {
+			password,
+			tokens: Mutex::new(tokens),
+			sessions: Mutex::new(HashMap::new()),
+			nonces: Mutex::new(HashMap::new()),
+			failures: Mutex::new(HashMap::new()),
+		})
+	}
+
+	pub fn check_password(&self, password: &str) -> bool {
+		self.password.as_ref().is_none_or(|hash| hash.verify(password))
+	}
+
+	/// Returns the public hash parameters clients need to derive the signing key
+	pub fn params(&self) -> Option<String> {
+		self.password.as_ref().map(|hash| hash.params.to_string())
+	}
+
+	/// Returns who made the request if it is signed with the password or an API token, has a valid session cookie
+	/// or has the plain password or token while legacy authentication is enabled
+	pub fn authenticate(&self, method: &Method, path: &str, headers: &HeaderMap, body: &[u8]) -> Option<Identity> {
+		let Some(hash) = &self.password else {
+			return Some(Identity::Anonymous);
+		};
+
+		let authorization = headers
+			.get(header::AUTHORIZATION)
+			.and_then(|v| v.to_str().ok())
+			.unwrap_or_default();
+
+		if let Ok(signature) = authorization.parse::<Signature>() {
+			let (stored_key, identity) = match &signature.token {
+				Some(id) => {
+					let (name, token) = self.find_token(|token| &token.id == id)?;
+					(
+						token.stored_key(),
+						Identity::Token {
+							name,
+							scope: token.scope,
+						},
+					)
+				}
+				None => (hash.stored_key().to_vec(), Identity::Password),
+			};
+
+			let request = SignedRequest {
+				method: method.as_str(),
+				path,
+				content_type: headers
+					.get(header::CONTENT_TYPE)
+					.and_then(|v| v.to_str().ok())
+					.unwrap_or_default(),
+				body,
+			};
+
+			let valid = signature.verify(&stored_key, &request) && self.use_nonce(&signature.nonce);
+			return valid.then_some(identity);
+		}
+
+		if !authorization.is_empty() && Config::new().legacy_auth {
+			if let Some(id) = tokens::get_id(authorization) {
+				return self
+					.find_token(|token| token.id == id && token.verify(authorization))
+					.map(|(name, token)| Identity::Token {
+						name,
+						scope: token.scope,
+					});
+			}
+
+			if hash.verify(authorization) {
+				return Some(Identity::Password);
+			}
+		}
+
+		get_session(headers)
+			.is_some_and(|token| {
+				let mut sessions = self.sessions.lock().unwrap();
+				sessions.retain(|_, created| created.elapsed() < SESSION_DURATION);
+				sessions.contains_key(token)
+			})
+			.then_some(Identity::Session)
+	}
 
-pub async fn main(State(password): State<Option<String>>, request: Request<Body>, next: Next) -> impl IntoResponse {
-	let auth = request
+	/// Issues a new API token with the given name and scope and returns its value
+	pub fn create_token(&self, name: &str, scope: Scope) -> Result<String> {
+		ensure!(
+			!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+			"Token name can only contain letters, numbers, dashes and underscores"
+		);
+
+		let mut tokens = self.tokens.lock().unwrap();
+
+		if tokens.contains_key(name) {
+			bail!("Token {name} already exists");
+		}
+
+		let (token, value) = Token::generate(scope);
+		tokens.insert(name.to_owned(), token);
+
+		if let Err(err) = tokens::write(&tokens) {
+			tokens.remove(name);
+			return Err(err);
+		}
+
+		Ok(value)
+	}
+
+	pub fn tokens(&self) -> Tokens {
+		self.tokens.lock().unwrap().clone()
+	}
+
+	/// Revokes the API token with the given name, returns false if it does not exist
+	pub fn revoke_token(&self, name: &str) -> Result<bool> {
+		let mut tokens = self.tokens.lock().unwrap();
+
+		let Some(token) = tokens.remove(name) else {
+			return Ok(false);
+		};
+
+		if let Err(err) = tokens::write(&tokens) {
+			tokens.insert(name.to_owned(), token);
+			return Err(err);
+		}
+
+		Ok(true)
+	}
+
+	/// Creates a new session and returns its token
+	pub fn create_session(&self) -> String {
+		let token = rand::random::<[u8; 32]>()
+			.iter()
+			.map(|byte| format!("{byte:02x}"))
+			.collect::<String>();
+
+		self.sessions.lock().unwrap().insert(token.clone(), Instant::now());
+
+		token
+	}
+
+	pub fn remove_session(&self, headers: &HeaderMap) {
+		if let Some(token) = get_session(headers) {
+			self.sessions.lock().unwrap().remove(token);
+		}
+	}
+
+	/// Returns how much longer the address is locked out for, none if it is not locked out
+	pub fn lockout(&self, ip: IpAddr) -> Option<Duration> {
+		let failures = self.failures.lock().unwrap();
+		let locked_until = failures.get(&ip)?.locked_until?;
+
+		locked_until.checked_duration_since(Instant::now())
+	}
+
+	/// Returns all locked out addresses with how much longer they are locked out for
+	pub fn blocked(&self) -> Vec<(IpAddr, Duration)> {
+		let now = Instant::now();
+		let mut blocked = self
+			.failures
+			.lock()
+			.unwrap()
+			.iter()
+			.filter_map(|(ip, failures)| Some((*ip, failures.locked_until?.checked_duration_since(now)?)))
+			.collect::<Vec<_>>();
+
+		blocked.sort();
+		blocked
+	}
+
+	/// Records a failed authentication attempt and returns how long to delay the response,
+	/// which grows with each failure until the address is locked out
+	pub fn fail(&self, ip: IpAddr) -> Duration {
+		let config = Config::new();
+		let lockout = Duration::from_secs(config.auth_lockout as u64);
+		let now = Instant::now();
+
+		let mut failures = self.failures.lock().unwrap();
+		failures.retain(|_, failures| failures.last.elapsed() < lockout);
+
+		let failures = failures.entry(ip).or_insert(Failures {
+			count: 0,
+			last: now,
+			locked_until: None,
+		});
+
+		failures.count += 1;
+		failures.last = now;
+
+		if config.auth_max_failures != 0 && failures.count >= config.auth_max_failures {
+			racky_warn!(
+				"Locked out {ip} for {}s after {} failed authentication attempts",
+				lockout.as_secs(),
+				failures.count
+			);
+
+			failures.count = 0;
+			failures.locked_until = Some(now + lockout);
+
+			return Duration::ZERO;
+		}
+
+		Duration::from_millis(config.auth_delay as u64) * failures.count as u32
+	}
+
+	/// Forgets failed authentication attempts of the address after it authenticated successfully
+	pub fn succeed(&self, ip: IpAddr) {
+		self.failures.lock().unwrap().remove(&ip);
+	}
+
+	/// Returns the first API token matching the predicate with its name
+	fn find_token(&self, predicate: impl Fn(&Token) -> bool) -> Option<(String, Token)> {
+		self.tokens
+			.lock()
+			.unwrap()
+			.iter()
+			.find(|(_, token)| predicate(token))
+			.map(|(name, token)| (name.to_owned(), token.clone()))
+	}
+
+	/// Records the nonce of a signed request, returns false if it was already used
+	fn use_nonce(&self, nonce: &str) -> bool {
+		let mut nonces = self.nonces.lock().unwrap();
+
+		// Older signatures are rejected by their timestamp, so their nonces can be forgotten
+		nonces.retain(|_, used| used.elapsed() < MAX_CLOCK_SKEW * 2);
+		nonces.insert(nonce.to_owned(), Instant::now()).is_none()
+	}
+}
+
+pub async fn main(State(auth): State<AuthPtr>, mut request: Request<Body>, next: Next) -> impl IntoResponse {
+	if request.extensions().get::<LocalConnection>().is_some() {
+		request.extensions_mut().insert(Identity::Local);
+		return Ok(with_identity(next.run(request).await, Identity::Local));
+	}
+
+	let is_api = request.uri().path().starts_with("/api/");
+	let ip = get_ip(&request);
+
+	if let Some(remaining) = ip.and_then(|ip| auth.lockout(ip)) {
+		return Err(locked_out(remaining, is_api));
+	}
+
+	// Requests without any credentials are not failed attempts, the dashboard makes them before logging in
+	let has_credentials =
+		request.headers().contains_key(header::AUTHORIZATION) || get_session(request.headers()).is_some();
+
+	let is_signed = request
 		.headers()
-		.get("Authorization")
+		.get(header::AUTHORIZATION)
 		.and_then(|v| v.to_str().ok())
-		.unwrap_or_default();
+		.is_some_and(|authorization| authorization.parse::<Signature>().is_ok());
+
+	// Signatures cover the body, so it has to be read before the request can be authenticated
+	let (parts, mut body) = request.into_parts();
+	let mut bytes = Bytes::new();
+
+	if is_signed {
+		bytes = match body::to_bytes(body, MAX_BODY_SIZE).await {
+			Ok(bytes) => bytes,
+			Err(err) => return Err(response!(PAYLOAD_TOO_LARGE, "Failed to read request body: {err}")),
+		};
+		body = Body::from(bytes.clone());
+	}
+
+	let path = parts
+		.uri
+		.path_and_query()
+		.map(|path| path.as_str())
+		.unwrap_or_else(|| parts.uri.path());
+
+	let Some(identity) = auth.authenticate(&parts.method, path, &parts.headers, &bytes) else {
+		if let Some(ip) = ip
+			&& has_credentials
+		{
+			time::sleep(auth.fail(ip)).await;
+
+			if let Some(remaining) = auth.lockout(ip) {
+				return Err(locked_out(remaining, is_api));
+			}
+		}
+
+		return Err(if is_api {
+			api::Error::unauthorized().into_response()
+		} else {
+			StatusCode::UNAUTHORIZED.into_response()
+		});
+	};
 
-	if let Some(password) = password
-		&& auth != password
+	if let Some(ip) = ip
+		&& has_credentials
 	{
-		return Err(StatusCode::UNAUTHORIZED);
+		auth.succeed(ip);
+	}
+
+	let mut request = Request::from_parts(parts, body);
+	request.extensions_mut().insert(identity.clone());
+
+	Ok(with_identity(next.run(request).await, identity))
+}
+
+/// Attaches the identity to the response, so the audit layer which runs outside this one can record it
+pub fn with_identity(mut response: Response, identity: Identity) -> Response {
+	response.extensions_mut().insert(identity);
+	response
+}
+
+/// Rejects requests whose scope does not grant the one required by the route
+pub async fn require(State(required): State<Scope>, request: Request<Body>, next: Next) -> impl IntoResponse {
+	let scope = request.extensions().get::<Identity>().map(Identity::scope);
+
+	// Nested routers only see the path relative to where they are nested
+	let path = request
+		.extensions()
+		.get::<OriginalUri>()
+		.map_or_else(|| request.uri().path(), |uri| uri.path());
+
+	if scope.is_none_or(|scope| scope < required) {
+		return Err(if path.starts_with("/api/") {
+			api::Error::forbidden(required).into_response()
+		} else {
+			response!(FORBIDDEN, "This request requires the {required} scope")
+		});
 	}
 
 	Ok(next.run(request).await)
 }
+
+/// Returns the address of the client, none if the server does not provide connection info
+fn get_ip<B>(request: &Request<B>) -> Option<IpAddr> {
+	request
+		.extensions()
+		.get::<ConnectInfo<SocketAddr>>()
+		.map(|ConnectInfo(address)| address.ip())
+}
+
+pub fn locked_out(remaining: Duration, is_api: bool) -> Response {
+	let message = format!(
+		"Too many failed authentication attempts, try again in {}s",
+		remaining.as_secs() + 1
+	);
+	let retry_after = [(header::RETRY_AFTER, (remaining.as_secs() + 1).to_string())];
+
+	if is_api {
+		(
+			retry_after,
+			api::Error::new(StatusCode::TOO_MANY_REQUESTS, "too_many_requests", message),
+		)
+			.into_response()
+	} else {
+		(retry_after, response!(TOO_MANY_REQUESTS, message)).into_response()
+	}
+}
+
+fn get_session(headers: &HeaderMap) -> Option<&str> {
+	headers
+		.get_all(header::COOKIE)
+		.iter()
+		.filter_map(|cookie| cookie.to_str().ok())
+		.flat_map(|cookie| cookie.split(';'))
+		.find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('='))
+}
+
+#[cfg(test)]
+mod tests {
+	use axum::http::HeaderValue;
+
+	use super::*;
+
+	const PATH: &str = "/api/programs/web/start";
+
+	fn sign(client_key: &[u8], body: &[u8]) -> HeaderMap {
+		sign_with_token(client_key, None, body)
+	}
+
+	fn sign_with_token(client_key: &[u8], token: Option<&str>, body: &[u8]) -> HeaderMap {
+		let request = SignedRequest {
+			method: "POST",
+			path: PATH,
+			content_type: "",
+			body,
+		};
+
+		let mut headers = HeaderMap::new();
+		let signature = Signature::new(client_key, token, &request).to_string();
+		headers.insert(header::AUTHORIZATION, HeaderValue::from_str(&signature).unwrap());
+		headers
+	}
+
+	#[test]
+	fn signed_request_is_authenticated() {
+		let hash = PasswordHash::new("secret");
+		let client_key = hash.params.client_key("secret");
+		let auth = Auth::new(Some(hash), Tokens::new());
+
+		let headers = sign(&client_key, b"a=1");
+		assert_eq!(
+			auth.authenticate(&Method::POST, PATH, &headers, b"a=1"),
+			Some(Identity::Password)
+		);
+
+		let headers = sign(&client_key, b"a=1");
+		assert_eq!(auth.authenticate(&Method::POST, PATH, &headers, b"a=2"), None);
+		assert_eq!(
+			auth.authenticate(&Method::GET, PATH, &sign(&client_key, b""), b""),
+			None
+		);
+	}
+
+	#[test]
+	fn replayed_nonce_is_rejected() {
+		let hash = PasswordHash::new("secret");
+		let client_key = hash.params.client_key("secret");
+		let auth = Auth::new(Some(hash), Tokens::new());
+		let headers = sign(&client_key, b"");
+
+		assert!(auth.authenticate(&Method::POST, PATH, &headers, b"").is_some());
+		assert!(auth.authenticate(&Method::POST, PATH, &headers, b"").is_none());
+
+		assert!(auth.use_nonce("0123"));
+		assert!(!auth.use_nonce("0123"));
+		assert!(auth.use_nonce("4567"));
+	}
+
+	#[test]
+	fn token_grants_its_scope() {
+		let auth = Auth::new(Some(PasswordHash::new("secret")), Tokens::new());
+
+		for (name, scope) in [("reader", Scope::Read), ("deployer", Scope::Deploy)] {
+			let (token, value) = Token::generate(scope);
+			let headers = sign_with_token(&tokens::client_key(&value), Some(&token.id), b"");
+
+			auth.tokens.lock().unwrap().insert(name.to_owned(), token);
+			let identity = auth.authenticate(&Method::POST, PATH, &headers, b"").unwrap();
+
+			assert_eq!(
+				identity,
+				Identity::Token {
+					name: name.to_owned(),
+					scope
+				}
+			);
+			assert_eq!(identity.scope(), scope);
+		}
+
+		assert_eq!(Identity::Password.scope(), Scope::Admin);
+	}
+
+	#[test]
+	fn unknown_token_is_rejected() {
+		let auth = Auth::new(Some(PasswordHash::new("secret")), Tokens::new());
+		let (token, value) = Token::generate(Scope::Admin);
+		let headers = sign_with_token(&tokens::client_key(&value), Some(&token.id), b"");
+
+		assert_eq!(auth.authenticate(&Method::POST, PATH, &headers, b""), None);
+	}
+
+	#[test]
+	fn server_without_password_is_anonymous() {
+		let auth = Auth::new(None, Tokens::new());
+		assert_eq!(
+			auth.authenticate(&Method::POST, PATH, &HeaderMap::new(), b""),
+			Some(Identity::Anonymous)
+		);
+	}
+}
@@ -1,15 +1,44 @@
-use std::{io::Result, net::TcpListener};
+use std::{
+	convert::Infallible,
+	io::Result,
+	net::{SocketAddr, TcpListener},
+	path::PathBuf,
+	sync::Arc,
+};
 
 use axum::{
-	Router,
-	middleware::from_fn_with_state,
-	response::Redirect,
+	Extension, Router,
+	http::HeaderValue,
+	middleware::{from_fn, from_fn_with_state, map_response},
+	response::{
+		IntoResponse, Response,
+		sse::{Event, KeepAlive, Sse},
+	},
 	routing::{get, post},
+	serve::ListenerExt,
 };
-use tokio::net;
+use colored::Colorize;
+use futures_util::stream;
+use rustls::ServerConfig;
+use tokio::{net, sync::mpsc, task};
 
-use crate::{consts::BODY_SIZE_LIMIT, core::CorePtr};
+use crate::{
+	auth::PasswordHash,
+	consts::{BODY_SIZE_LIMIT, VERSION_HEADER},
+	core::CorePtr,
+	logger, racky_error, racky_info, racky_warn,
+	tokens::{Scope, Tokens},
+	web::{
+		listener::TlsListener,
+		middleware::auth::{Auth, LocalConnection},
+	},
+};
 
+mod api;
+mod auth;
+mod dashboard;
+mod listener;
+mod metrics;
 mod middleware;
 mod ping;
 mod program;
@@ -32,54 +61,171 @@ pub struct Web {
 	router: Router,
 	address: String,
 	port: u16,
+	tls: Option<Arc<ServerConfig>>,
+	socket: Option<PathBuf>,
 }
 
 impl Web {
-	pub fn new(core: CorePtr, address: &str, port: u16, password: Option<String>) -> Self {
-		let router = Router::new()
-			.route("/", get(|| async { Redirect::to("/server/status") }))
-			.route("/ping", get(ping::main))
-			// Program routes
-			.route("/program/add", post(program::add::main).layer(BODY_SIZE_LIMIT))
+	pub fn new(core: CorePtr, address: &str, port: u16, password: Option<PasswordHash>, tokens: Tokens) -> Self {
+		let auth = Auth::new(password, tokens);
+
+		let operator = Router::new()
 			.route("/program/config", post(program::config::main))
-			.route("/program/list", get(program::list::main))
-			.route("/program/logs", get(program::logs::main))
-			.route("/program/remove", post(program::remove::main))
 			.route("/program/restart", post(program::restart::main))
 			.route("/program/start", post(program::start::main))
-			.route("/program/status", get(program::status::main))
 			.route("/program/stop", post(program::stop::main))
+			.route_layer(from_fn_with_state(Scope::Operator, middleware::auth::require));
+
+		let deploy = Router::new()
+			.route("/program/add", post(program::add::main).layer(BODY_SIZE_LIMIT))
+			.route("/program/remove", post(program::remove::main))
 			.route("/program/update", post(program::update::main).layer(BODY_SIZE_LIMIT))
-			// Server routes
+			.route_layer(from_fn_with_state(Scope::Deploy, middleware::auth::require));
+
+		let admin = Router::new()
+			.route("/server/audit", get(server::audit::main))
 			.route("/server/config", post(server::config::main))
-			.route("/server/logs", get(server::logs::main))
 			.route("/server/reboot", post(server::reboot::main))
 			.route("/server/restart", post(server::restart::main))
 			.route("/server/shutdown", post(server::shutdown::main))
-			.route("/server/status", get(server::status::main))
 			.route("/server/stop", post(server::stop::main))
+			.route(
+				"/server/token/create",
+				post(server::token::create).with_state(auth.clone()),
+			)
+			.route("/server/token/list", get(server::token::list).with_state(auth.clone()))
+			.route(
+				"/server/token/revoke",
+				post(server::token::revoke).with_state(auth.clone()),
+			)
 			.route("/server/update", post(server::update::main))
-			// Middleware
-			.layer(from_fn_with_state(password, middleware::auth::main))
+			.route_layer(from_fn_with_state(Scope::Admin, middleware::auth::require));
+
+		let router = Router::new()
+			.route("/ping", get(ping::main))
+			// Read-only routes, allowed for every scope
+			.route("/program/list", get(program::list::main))
+			.route("/program/logs", get(program::logs::main))
+			.route("/program/logs/follow", get(program::logs::follow))
+			.route("/program/status", get(program::status::main))
+			.route("/server/events", get(server::events::main))
+			.route("/server/logs", get(server::logs::main))
+			.route("/server/logs/follow", get(server::logs::follow))
+			.route(
+				"/server/status",
+				get(server::status::main).layer(Extension(auth.clone())),
+			)
+			// Routes that require a higher scope
+			.merge(operator)
+			.merge(deploy)
+			.merge(admin)
+			// JSON API routes
+			.nest("/api/v1", api::router())
+			// Authentication, the dashboard and metrics routes below handle it themselves
+			.layer(from_fn_with_state(auth.clone(), middleware::auth::main))
+			// Signing parameters, needed by clients before they can authenticate
+			.route("/auth", get(auth::main).with_state(auth.clone()))
+			// Dashboard routes, they handle authentication themselves
+			.route("/", get(dashboard::main))
+			.route("/login", post(dashboard::login).with_state(auth.clone()))
+			.route("/logout", post(dashboard::logout).with_state(auth.clone()))
+			// Metrics route, also accepts its own token
+			.route(
+				"/metrics",
+				get(metrics::main).layer(from_fn_with_state(auth, metrics::authorize)),
+			)
+			// Audit runs before authentication, so rejected requests and logins are recorded too
+			.layer(from_fn(middleware::audit::main))
+			.layer(map_response(version_header))
 			.with_state(core);
 
 		Self {
 			router,
 			address: address.to_owned(),
 			port,
+			tls: None,
+			socket: None,
 		}
 	}
 
+	/// Serves HTTPS with the given config instead of plain HTTP
+	pub fn with_tls(mut self, config: Arc<ServerConfig>) -> Self {
+		self.tls = Some(config);
+		self
+	}
+
+	/// Also serves local clients on the Unix socket at the given path
+	pub fn with_socket(mut self, path: PathBuf) -> Self {
+		self.socket = Some(path);
+		self
+	}
+
 	#[tokio::main]
 	pub async fn serve(self) -> Result<()> {
-		axum::serve(
-			net::TcpListener::bind((self.address.as_str(), self.port)).await?,
-			self.router,
-		)
-		.await
+		#[cfg(unix)]
+		if let Some(path) = &self.socket {
+			match listener::bind_socket(path) {
+				Ok(listener) => {
+					let router = self.router.clone().layer(Extension(LocalConnection));
+
+					racky_info!("Listening for local clients on {}", path.display().to_string().bold());
+
+					tokio::spawn(async move {
+						if let Err(err) = axum::serve(listener, router).await {
+							racky_error!("Unix socket stopped: {err}");
+						}
+					});
+				}
+				Err(err) => racky_warn!("Failed to listen on Unix socket {}: {err:#}", path.display()),
+			}
+		}
+
+		// Client addresses are needed to track failed authentication attempts
+		let service = self.router.into_make_service_with_connect_info::<SocketAddr>();
+
+		match self.tls {
+			Some(config) => {
+				// Tapping the listener lets axum provide the connect info of custom listeners
+				let listener = TlsListener::bind(&self.address, self.port, config)
+					.await?
+					.tap_io(|_| ());
+				axum::serve(listener, service).await
+			}
+			None => {
+				axum::serve(
+					net::TcpListener::bind((self.address.as_str(), self.port)).await?,
+					service,
+				)
+				.await
+			}
+		}
 	}
 
 	pub fn is_port_free(&self) -> bool {
 		TcpListener::bind((self.address.as_str(), self.port)).is_ok()
 	}
 }
+
+/// Tells clients which version of Racky the server runs
+async fn version_header(mut response: Response) -> Response {
+	response
+		.headers_mut()
+		.insert(VERSION_HEADER, HeaderValue::from_static(env!("CARGO_PKG_VERSION")));
+
+	response
+}
+
+/// Streams lines of the given log directory as server-sent events until the client disconnects
+fn follow_logs(path: PathBuf, lines: usize) -> Response {
+	let (tx, rx) = mpsc::channel(100);
+
+	// Followers end as soon as the client disconnects and the stream with the receiver is dropped
+	task::spawn_blocking(move || logger::follow(&path, lines, tx));
+
+	let stream = stream::unfold(rx, |mut rx| async move {
+		let line = rx.recv().await?;
+		Some((Ok::<_, Infallible>(Event::default().data(line)), rx))
+	});
+
+	Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
@@ -5,10 +5,13 @@ use axum::{
 	response::IntoResponse,
 };
 use log::{trace, warn};
+use tokio::task;
 
 use crate::{
 	core::{CorePtr, program::Program},
-	dirs, response, zip,
+	dirs, response,
+	web::program::config::track_scheduled,
+	zip,
 };
 
 pub async fn main(State(core): State<CorePtr>, mut multipart: Multipart) -> impl IntoResponse {
@@ -84,13 +87,14 @@ pub async fn main(State(core): State<CorePtr>, mut multipart: Multipart) -> impl
 	}
 
 	if !program.config().auto_start {
+		track_scheduled(&core, &program);
 		return response!(OK, "Program {name} added successfully{message}");
 	}
 
-	let started = core
-		.add_program(&program)
-		.and_then(|_| core.start_program(&program))
-		.is_ok();
+	let started = matches!(
+		task::spawn_blocking(move || core.add_program(&program).and_then(|_| core.start_program(&program))).await,
+		Ok(Ok(()))
+	);
 
 	if !started || !message.is_empty() {
 		message.push_str(". See server logs for more details!");
@@ -1,24 +1,49 @@
-use axum::{Form, response::IntoResponse};
+use axum::{Form, extract::State, response::IntoResponse};
 use documented::DocumentedFields;
-use serde::Deserialize;
 
 use crate::{
-	core::program::{Config, Program},
+	core::{
+		CorePtr,
+		program::{Config, Program, ProgramPtr},
+	},
 	logger::Table,
 	response,
 };
 
-#[derive(Debug, Default, Deserialize)]
-#[serde(default)]
+#[derive(Debug, Default)]
 pub struct Request {
 	program: String,
-	data: String,
+	/// Key=value pairs, each sent as a separate field as values can contain commas
+	data: Vec<String>,
 	default: bool,
 	list: bool,
 }
 
-pub async fn main(Form(request): Form<Request>) -> impl IntoResponse {
-	let program = Program::new(&request.program);
+impl From<Vec<(String, String)>> for Request {
+	fn from(fields: Vec<(String, String)>) -> Self {
+		let mut request = Self::default();
+
+		for (key, value) in fields {
+			match key.as_str() {
+				"program" => request.program = value,
+				"data" => request.data.push(value),
+				"default" => request.default = value == "true",
+				"list" => request.list = value == "true",
+				_ => (),
+			}
+		}
+
+		request
+	}
+}
+
+pub async fn main(State(core): State<CorePtr>, Form(fields): Form<Vec<(String, String)>>) -> impl IntoResponse {
+	let request = Request::from(fields);
+
+	// Programs that are already tracked get their configuration updated in place
+	let program = core
+		.get_program(&request.program)
+		.unwrap_or_else(|| Program::new(&request.program));
 
 	if !program.paths().validate() {
 		return response!(NOT_FOUND, "Program {} does not exist", request.program);
@@ -76,11 +101,14 @@ pub async fn main(Form(request): Form<Request>) -> impl IntoResponse {
 		drop(state);
 
 		return match program.save_config() {
-			Ok(()) => response!(
-				OK,
-				"Configuration of {} restored to defaults successfully",
-				request.program
-			),
+			Ok(()) => {
+				track_scheduled(&core, &program);
+				response!(
+					OK,
+					"Configuration of {} restored to defaults successfully",
+					request.program
+				)
+			}
 			Err(err) => response!(
 				INTERNAL_SERVER_ERROR,
 				"Failed to save {} configuration: {err}",
@@ -93,34 +121,46 @@ pub async fn main(Form(request): Form<Request>) -> impl IntoResponse {
 		return response!(BAD_REQUEST, "No key=value pairs provided");
 	}
 
-	let mut changed = 0;
+	// Every pair is validated before any of them is applied, so a bad one does not leave a partial update behind
+	let mut validated = config.clone();
+	let mut updates = Vec::with_capacity(request.data.len());
 
-	for pair in request.data.split(',') {
-		let (key, value) = if let Some(pair) = pair.split_once('=') {
-			pair
-		} else {
+	for pair in &request.data {
+		let Some((key, value)) = pair.split_once('=') else {
 			return response!(BAD_REQUEST, "Invalid key=value or key= pair: {}", pair);
 		};
 
-		let original = if let Some(original) = config.get(key) {
-			original
-		} else {
-			let original = program.state().vars.get(key).cloned();
+		let (key, value) = match Config::migrate(key, value) {
+			Some((key, value)) => (key, value),
+			None => (key, value.to_owned()),
+		};
 
-			program.update_config(key, value).unwrap();
+		// Anything that is not a setting is a variable, which accepts any value
+		let Some(default) = defaults.get(key) else {
+			updates.push((key, value));
+			continue;
+		};
 
-			if original.is_none() || original.unwrap() != value {
-				changed += 1;
-			}
+		let value = if value.is_empty() { default.to_string() } else { value };
 
-			continue;
+		if let Err(err) = validated.set(key, &value) {
+			return response!(BAD_REQUEST, "Failed to set `{key}` to `{value}`: {err}");
+		}
+
+		updates.push((key, value));
+	}
+
+	let mut changed = 0;
+
+	for (key, value) in updates {
+		let get = |program: &ProgramPtr| match program.config().get(key) {
+			Some(value) => Some(value.to_string()),
+			None => program.state().vars.get(key).cloned(),
 		};
 
-		if value.is_empty() {
-			program
-				.update_config(key, &defaults.get(key).unwrap().to_string())
-				.unwrap();
-		} else if let Err(err) = program.update_config(key, value) {
+		let original = get(&program);
+
+		if let Err(err) = program.update_config(key, &value) {
 			return response!(
 				INTERNAL_SERVER_ERROR,
 				"Failed to update {} configuration: {err}",
@@ -128,19 +168,22 @@ pub async fn main(Form(request): Form<Request>) -> impl IntoResponse {
 			);
 		}
 
-		if program.config().get(key).unwrap() != original {
+		if get(&program) != original {
 			changed += 1;
 		}
 	}
 
 	match program.save_config() {
-		Ok(()) => response!(
-			OK,
-			format!(
-				"Configuration of {} updated successfully ({changed} changed)",
-				request.program
+		Ok(()) => {
+			track_scheduled(&core, &program);
+			response!(
+				OK,
+				format!(
+					"Configuration of {} updated successfully ({changed} changed)",
+					request.program
+				)
 			)
-		),
+		}
 		Err(err) => response!(
 			INTERNAL_SERVER_ERROR,
 			"Failed to save {} configuration: {err}",
@@ -148,3 +191,10 @@ pub async fn main(Form(request): Form<Request>) -> impl IntoResponse {
 		),
 	}
 }
+
+/// Adds newly scheduled programs to the core so the scheduler can start them
+pub fn track_scheduled(core: &CorePtr, program: &ProgramPtr) {
+	if !program.config().schedule.is_none() && core.get_program(program.name()).is_none() {
+		core.add_program(program).ok();
+	}
+}
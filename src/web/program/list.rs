@@ -1,26 +1,30 @@
-use std::fs;
+use std::{collections::BTreeMap, fs};
 
+use anyhow::Result;
 use axum::{extract::State, response::IntoResponse};
 use jiff::SignedDuration;
 use log::error;
 
 use crate::{
-	core::{CorePtr, program::Program},
+	core::{
+		CorePtr,
+		program::{Program, ProgramPtr},
+	},
 	dirs,
-	ext::PathExt,
+	ext::{PathExt, ResultExt},
 	logger::Table,
 	response, util,
 };
 
-pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
-	let mut programs = core.programs().clone();
-
-	let entries = match fs::read_dir(dirs::bin()) {
-		Ok(entries) => entries,
-		Err(err) => return response!(INTERNAL_SERVER_ERROR, "Failed to read bin directory: {err}"),
-	};
+/// Returns loaded programs together with installed programs that have not been loaded yet, sorted by name
+pub fn collect(core: &CorePtr) -> Result<BTreeMap<String, ProgramPtr>> {
+	let mut programs = core
+		.programs()
+		.iter()
+		.map(|(name, program)| (name.to_owned(), program.clone()))
+		.collect::<BTreeMap<_, _>>();
 
-	for entry in entries {
+	for entry in fs::read_dir(dirs::bin()).desc("Failed to read bin directory")? {
 		if let Err(err) = entry {
 			error!("Failed to check program binary: {err}");
 			continue;
@@ -42,12 +46,28 @@ pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
 		programs.insert(program.name().to_owned(), program);
 	}
 
+	Ok(programs)
+}
+
+pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
+	let programs = match collect(&core) {
+		Ok(programs) => programs,
+		Err(err) => return response!(INTERNAL_SERVER_ERROR, "{err}"),
+	};
+
 	if programs.is_empty() {
 		return response!(NOT_FOUND, "There are no installed programs on the server");
 	}
 
 	let mut table = Table::new();
-	table.set_header(vec!["Name", "Status", "Executions", "Runtime", "Start Time"]);
+	table.set_header(vec![
+		"Name",
+		"Status",
+		"Executions",
+		"Runtime",
+		"Start Time",
+		"Next Run",
+	]);
 
 	for (name, program) in programs.iter() {
 		let state = program.state();
@@ -63,6 +83,10 @@ pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
 				.current
 				.map(|time| util::timestamp(Some(time)))
 				.unwrap_or_else(|| String::from("N/A")),
+			state
+				.next_run
+				.map(|time| util::timestamp(Some(time)))
+				.unwrap_or_else(|| String::from("N/A")),
 		]);
 	}
 
@@ -1,7 +1,16 @@
-use axum::{extract::Query, response::IntoResponse};
+use std::path::PathBuf;
+
+use axum::{
+	extract::{Query, State},
+	response::IntoResponse,
+};
 use serde::Deserialize;
 
-use crate::{dirs, logger, response};
+use crate::{
+	core::{CorePtr, program::Program},
+	logger, response,
+	web::follow_logs,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct Request {
@@ -9,15 +18,37 @@ pub struct Request {
 	page: Option<usize>,
 }
 
-pub async fn main(Query(request): Query<Request>) -> impl IntoResponse {
-	let path = dirs::logs().join(&request.program);
+#[derive(Debug, Deserialize)]
+pub struct FollowRequest {
+	program: String,
+	lines: Option<usize>,
+}
 
-	if !path.exists() {
+pub async fn main(State(core): State<CorePtr>, Query(request): Query<Request>) -> impl IntoResponse {
+	let Some(path) = get_logs(&core, &request.program) else {
 		return response!(NOT_FOUND, "Program {} does not exist", request.program);
-	}
+	};
 
 	match logger::read_file(&path, request.page.unwrap_or_default()) {
 		Ok(logs) => response!(OK, logs),
 		Err(error) => response!(BAD_REQUEST, format!("Failed to get {} logs: {error}", request.program)),
 	}
 }
+
+pub async fn follow(State(core): State<CorePtr>, Query(request): Query<FollowRequest>) -> impl IntoResponse {
+	let Some(path) = get_logs(&core, &request.program) else {
+		return response!(NOT_FOUND, "Program {} does not exist", request.program);
+	};
+
+	follow_logs(path, request.lines.unwrap_or(10))
+}
+
+/// Returns the logs directory of an existing program, if it has one
+fn get_logs(core: &CorePtr, name: &str) -> Option<PathBuf> {
+	core.get_program(name)
+		.or_else(|| {
+			Some(Program::new(name)).filter(|program| Program::is_valid_name(name) && program.paths().validate())
+		})
+		.map(|program| program.paths().logs.clone())
+		.filter(|path| path.exists())
+}
@@ -1,6 +1,7 @@
 use std::fs;
 
 use axum::{Form, extract::State, response::IntoResponse};
+use tokio::task;
 
 use crate::{
 	core::{CorePtr, program::Paths},
@@ -52,11 +53,26 @@ pub async fn main(State(core): State<CorePtr>, Form(request): Form<ProgramReques
 	if let Some(program) = core.get_program(&request.program) {
 		core.remove_program(&program).expect("Failed to remove program");
 
-		if program.is_active() && core.stop_program(&program).is_err() {
+		if program.is_active()
+			&& !matches!(
+				task::spawn_blocking(move || core.stop_program(&program)).await,
+				Ok(Ok(_))
+			) {
 			message.push_str(" and failed to stop the process");
 		}
 	}
 
+	// Stopping saves the program state, so it can only be removed afterwards
+	if paths.state.exists() && fs::remove_file(&paths.state).is_err() {
+		message.push_str(" and failed to remove the state file");
+	}
+
+	for path in paths.get_output() {
+		if path.exists() && fs::remove_file(&path).is_err() {
+			message.push_str(" and failed to remove an output FIFO");
+		}
+	}
+
 	if !message.is_empty() {
 		message.push_str(". See server logs for more details!");
 	}
@@ -1,4 +1,5 @@
 use axum::{Form, extract::State, response::IntoResponse};
+use tokio::task;
 
 use crate::{
 	core::{CorePtr, program::Program},
@@ -25,12 +26,13 @@ pub async fn main(State(core): State<CorePtr>, Form(request): Form<ProgramReques
 		core.add_program(&program).expect("Failed to add program");
 	}
 
-	match core.start_program(&program) {
-		Ok(()) => response!(OK, "Program {} started successfully", request.program),
-		Err(err) => response!(
+	match task::spawn_blocking(move || core.start_program(&program)).await {
+		Ok(Ok(())) => response!(OK, "Program {} started successfully", request.program),
+		Ok(Err(err)) => response!(
 			INTERNAL_SERVER_ERROR,
 			"Failed to start program {}: {err}",
 			request.program
 		),
+		Err(err) => response!(INTERNAL_SERVER_ERROR, "{err}"),
 	}
 }
@@ -10,11 +10,7 @@ pub async fn main(State(core): State<CorePtr>, Query(request): Query<ProgramRequ
 	let program = if let Some(program) = core.get_program(&request.program) {
 		program
 	} else {
-		return response!(
-			NOT_FOUND,
-			"Program {} has not been run since the server was started",
-			request.program
-		);
+		return response!(NOT_FOUND, "Program {} has not been run yet", request.program);
 	};
 
 	let state = program.state();
@@ -24,6 +20,94 @@ pub async fn main(State(core): State<CorePtr>, Query(request): Query<ProgramRequ
 	let mut response = format!("Name: {}\n", request.program);
 	response.push_str(&format!("Status: {}\n", state.status));
 	response.push_str(&format!("Executions: {}\n", state.executions));
+	response.push_str(&format!(
+		"Last Exit Code: {}\n",
+		state
+			.exit_code
+			.map(|code| code.to_string())
+			.unwrap_or_else(|| String::from("N/A"))
+	));
+	response.push_str(&format!("Restart Policy: {}\n", config.restart_policy));
+	response.push_str(&format!(
+		"Arguments: {}\n",
+		if config.args.is_empty() {
+			String::from("N/A")
+		} else {
+			config.args.to_string()
+		}
+	));
+	if !config.depends_on.is_empty() {
+		response.push_str(&format!(
+			"Dependencies: {}\n",
+			config
+				.depends_on
+				.iter()
+				.map(|name| match core.get_program(name) {
+					Some(dependency) => format!("{name} ({})", dependency.status()),
+					None => format!("{name} (Idle)"),
+				})
+				.collect::<Vec<_>>()
+				.join(", ")
+		));
+	}
+
+	if !config.schedule.is_none() {
+		response.push_str(&format!(
+			"Schedule: {} (next run: {})\n",
+			config.schedule,
+			state
+				.next_run
+				.map(|time| util::timestamp(Some(time)))
+				.unwrap_or_else(|| String::from("N/A"))
+		));
+	}
+
+	if !config.user.is_empty() || !config.group.is_empty() {
+		response.push_str(&format!(
+			"User: {}{}\n",
+			if config.user.is_empty() { "N/A" } else { &config.user },
+			if config.group.is_empty() {
+				String::new()
+			} else {
+				format!(" (group: {})", config.group)
+			}
+		));
+	}
+
+	let limits = [
+		(!config.memory_limit.is_none()).then(|| format!("memory {}", config.memory_limit)),
+		(config.cpu_limit > 0.0).then(|| format!("CPU {}", config.cpu_limit)),
+		(config.open_files_limit > 0).then(|| format!("open files {}", config.open_files_limit)),
+		(config.processes_limit > 0).then(|| format!("processes {}", config.processes_limit)),
+	]
+	.into_iter()
+	.flatten()
+	.collect::<Vec<_>>();
+
+	if !limits.is_empty() {
+		response.push_str(&format!("Limits: {}\n", limits.join(", ")));
+	}
+
+	if config.watch {
+		response.push_str(&format!(
+			"Watch: {}{}\n",
+			if config.watch_include.is_empty() {
+				String::from("all files")
+			} else {
+				config.watch_include.to_string()
+			},
+			if config.watch_ignore.is_empty() {
+				String::new()
+			} else {
+				format!(" (ignoring {})", config.watch_ignore)
+			}
+		));
+	}
+
+	if !config.health_check.is_none() {
+		response.push_str(&format!("Health: {} ({})\n", state.health, config.health_check));
+	}
+
 	response.push('\n');
 
 	response.push_str("Current:\n");
@@ -60,5 +144,13 @@ pub async fn main(State(core): State<CorePtr>, Query(request): Query<ProgramRequ
 			.unwrap_or_else(|| String::from("N/A"))
 	));
 
+	if !state.history.is_empty() {
+		response.push_str("\nHistory:\n");
+
+		for run in state.history.iter().rev() {
+			response.push_str(&format!("  {:.0} - {:.0}: {}\n", run.started, run.ended, run.status));
+		}
+	}
+
 	response!(OK, response)
 }
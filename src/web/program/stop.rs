@@ -1,4 +1,5 @@
 use axum::{Form, extract::State, response::IntoResponse};
+use tokio::task;
 
 use crate::{core::CorePtr, response, web::program::ProgramRequest};
 
@@ -11,12 +12,13 @@ pub async fn main(State(core): State<CorePtr>, Form(request): Form<ProgramReques
 		return response!(BAD_REQUEST, "Program {} is not running", request.program);
 	};
 
-	match core.stop_program(&program) {
-		Ok(()) => response!(OK, "Program {} stopped successfully", request.program),
-		Err(err) => response!(
+	match task::spawn_blocking(move || core.stop_program(&program)).await {
+		Ok(Ok(termination)) => response!(OK, "Program {} stopped {termination}", request.program),
+		Ok(Err(err)) => response!(
 			INTERNAL_SERVER_ERROR,
 			"Failed to stop program {}: {err}",
 			request.program
 		),
+		Err(err) => response!(INTERNAL_SERVER_ERROR, "{err}"),
 	}
 }
@@ -0,0 +1,120 @@
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use axum::{extract::Query, response::IntoResponse};
+use jiff::{Span, Timestamp, Zoned};
+use serde::Deserialize;
+use tokio::task;
+
+use crate::{
+	audit::{self, Entry},
+	logger::Table,
+	response, util,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Request {
+	program: String,
+	action: String,
+	identity: String,
+	ip: String,
+	since: String,
+	failed: bool,
+	limit: Option<usize>,
+}
+
+impl Request {
+	fn matches(&self, entry: &Entry, since: Option<Timestamp>) -> bool {
+		let program = entry
+			.params
+			.iter()
+			.find(|(key, _)| key == "program")
+			.map(|(_, value)| value.as_str())
+			.or_else(|| entry.path.strip_prefix("/api/v1/programs/")?.split('/').next());
+
+		(self.program.is_empty() || program == Some(self.program.as_str()))
+			&& (self.action.is_empty() || entry.path.rsplit('/').next() == Some(self.action.as_str()))
+			&& (self.identity.is_empty()
+				|| entry.identity == self.identity
+				|| entry.identity == format!("token:{}", self.identity))
+			&& (self.ip.is_empty() || entry.ip.is_some_and(|ip| ip.to_string() == self.ip))
+			&& since.is_none_or(|since| entry.timestamp >= since)
+			&& (!self.failed || entry.status >= 400)
+	}
+}
+
+pub async fn main(Query(request): Query<Request>) -> impl IntoResponse {
+	let since = match parse_since(&request.since) {
+		Ok(since) => since,
+		Err(err) => return response!(BAD_REQUEST, "{err}"),
+	};
+
+	let entries = match task::spawn_blocking(audit::read).await {
+		Ok(Ok(entries)) => entries,
+		Ok(Err(err)) => return response!(INTERNAL_SERVER_ERROR, "Failed to read audit log: {err}"),
+		Err(err) => return response!(INTERNAL_SERVER_ERROR, "Failed to read audit log: {err}"),
+	};
+
+	let entries = entries
+		.into_iter()
+		.filter(|entry| request.matches(entry, since))
+		.collect::<Vec<_>>();
+
+	if entries.is_empty() {
+		return response!(NOT_FOUND, "There are no matching audit log entries");
+	}
+
+	let skip = entries.len().saturating_sub(request.limit.unwrap_or(50));
+
+	let mut table = Table::new();
+	table.set_header(vec![
+		"Time",
+		"Address",
+		"Identity",
+		"User Agent",
+		"Request",
+		"Parameters",
+		"Status",
+	]);
+
+	for entry in entries.into_iter().skip(skip) {
+		table.add_row(vec![
+			util::timestamp(Some(SystemTime::from(entry.timestamp))),
+			entry.ip.map(|ip| ip.to_string()).unwrap_or_else(|| String::from("N/A")),
+			entry.identity,
+			entry.user_agent.unwrap_or_else(|| String::from("N/A")),
+			format!("{} {}", entry.method, entry.path),
+			entry
+				.params
+				.iter()
+				.map(|(key, value)| format!("{key}={value}"))
+				.collect::<Vec<_>>()
+				.join(" "),
+			entry.status.to_string(),
+		]);
+	}
+
+	response!(OK, table.to_string())
+}
+
+/// Parses a timestamp or a duration like `2h` that is subtracted from the current time
+fn parse_since(since: &str) -> Result<Option<Timestamp>> {
+	if since.is_empty() {
+		return Ok(None);
+	}
+
+	if let Ok(timestamp) = since.parse::<Timestamp>() {
+		return Ok(Some(timestamp));
+	}
+
+	let span = since
+		.parse::<Span>()
+		.with_context(|| format!("Invalid time `{since}`, expected a timestamp or a duration like `2h`"))?;
+
+	// Zoned arithmetic supports calendar units like days and months
+	Zoned::now()
+		.checked_sub(span)
+		.map(|time| Some(time.timestamp()))
+		.with_context(|| format!("Time `{since}` is too far in the past"))
+}
@@ -0,0 +1,32 @@
+use std::convert::Infallible;
+
+use axum::{
+	extract::State,
+	response::{
+		IntoResponse,
+		sse::{Event, KeepAlive, Sse},
+	},
+};
+use futures_util::stream;
+use log::warn;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::core::CorePtr;
+
+/// Streams program status changes as server-sent events with JSON data until the client disconnects
+pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
+	let stream = stream::unfold(core.subscribe(), |mut rx| async move {
+		loop {
+			match rx.recv().await {
+				Ok(event) => {
+					let data = Event::default().event("status").json_data(&event).unwrap_or_default();
+					return Some((Ok::<_, Infallible>(data), rx));
+				}
+				Err(RecvError::Lagged(skipped)) => warn!("Event subscriber fell behind and skipped {skipped} events"),
+				Err(RecvError::Closed) => return None,
+			}
+		}
+	});
+
+	Sse::new(stream).keep_alive(KeepAlive::default())
+}
@@ -1,16 +1,25 @@
 use axum::{extract::Query, response::IntoResponse};
 use serde::Deserialize;
 
-use crate::{dirs, logger, response};
+use crate::{dirs, logger, response, web::follow_logs};
 
 #[derive(Debug, Deserialize)]
 pub struct Request {
 	page: Option<usize>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct FollowRequest {
+	lines: Option<usize>,
+}
+
 pub async fn main(Query(request): Query<Request>) -> impl IntoResponse {
 	match logger::read_file(&dirs::logs().join("racky"), request.page.unwrap_or_default()) {
 		Ok(logs) => response!(OK, logs),
 		Err(err) => response!(BAD_REQUEST, "{err}"),
 	}
 }
+
+pub async fn follow(Query(request): Query<FollowRequest>) -> impl IntoResponse {
+	follow_logs(dirs::logs().join("racky"), request.lines.unwrap_or(10))
+}
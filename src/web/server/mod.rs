@@ -1,8 +1,11 @@
+pub mod audit;
 pub mod config;
+pub mod events;
 pub mod logs;
 pub mod reboot;
 pub mod restart;
 pub mod shutdown;
 pub mod status;
 pub mod stop;
+pub mod token;
 pub mod update;
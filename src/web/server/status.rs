@@ -1,75 +1,212 @@
 use std::time::{Duration, SystemTime};
 
-use axum::{extract::State, response::IntoResponse};
-use jiff::SignedDuration;
+use axum::{Extension, extract::State, response::IntoResponse};
+use jiff::{SignedDuration, Timestamp};
+use serde::Serialize;
 use sysinfo::{Components, Disks, System};
 
-use crate::{consts::GIGABYTE, core::CorePtr, response, util};
+use crate::{consts::GIGABYTE, core::CorePtr, response, util, web::middleware::auth::AuthPtr};
 
-pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
-	let programs = core.programs();
+#[derive(Debug, Serialize)]
+pub struct Status {
+	pub server: Server,
+	pub system: Machine,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Server {
+	pub version: String,
+	/// Uptime in seconds
+	pub uptime: u64,
+	pub start_time: Timestamp,
+	pub programs: usize,
+	pub running_programs: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Machine {
+	pub version: Option<String>,
+	/// Uptime in seconds
+	pub uptime: u64,
+	pub boot_time: Timestamp,
+	pub processes: usize,
+	pub cpus: Vec<Cpu>,
+	pub memory: Usage,
+	pub swap: Usage,
+	pub disks: Vec<Disk>,
+	pub temperatures: Vec<Temperature>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cpu {
+	pub name: String,
+	/// Usage in percent
+	pub usage: f32,
+	/// Frequency in MHz
+	pub frequency: u64,
+}
 
+/// Used and total amount in bytes
+#[derive(Debug, Serialize)]
+pub struct Usage {
+	pub used: u64,
+	pub total: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Disk {
+	pub name: String,
+	#[serde(flatten)]
+	pub usage: Usage,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Temperature {
+	pub label: String,
+	/// Temperature in degrees Celsius
+	pub value: Option<f32>,
+}
+
+pub fn collect(core: &CorePtr) -> Status {
 	let mut system = System::new_all();
 	system.refresh_all();
 
+	collect_with(core, &system)
+}
+
+/// Collects the status using an already refreshed system
+pub fn collect_with(core: &CorePtr, system: &System) -> Status {
+	let programs = core.programs();
+
 	let disks = Disks::new_with_refreshed_list();
 	let components = Components::new_with_refreshed_list();
 
-	let available_space = disks.iter().map(|disk| disk.available_space()).sum::<u64>();
-	let total_space = disks.iter().map(|disk| disk.total_space()).sum::<u64>();
+	let mut running_programs = programs
+		.iter()
+		.filter(|(_, program)| program.is_active())
+		.map(|(name, _)| name.to_owned())
+		.collect::<Vec<_>>();
+
+	running_programs.sort();
+
+	Status {
+		server: Server {
+			version: env!("CARGO_PKG_VERSION").to_owned(),
+			uptime: core.start_time().elapsed().unwrap_or_default().as_secs(),
+			start_time: Timestamp::try_from(*core.start_time()).unwrap_or_default(),
+			programs: programs.len(),
+			running_programs,
+		},
+		system: Machine {
+			version: System::long_os_version().or_else(System::os_version),
+			uptime: System::uptime(),
+			boot_time: Timestamp::try_from(SystemTime::UNIX_EPOCH + Duration::from_secs(System::boot_time()))
+				.unwrap_or_default(),
+			processes: system.processes().len(),
+			cpus: system
+				.cpus()
+				.iter()
+				.map(|cpu| Cpu {
+					name: cpu.name().to_owned(),
+					usage: cpu.cpu_usage(),
+					frequency: cpu.frequency(),
+				})
+				.collect(),
+			memory: Usage {
+				used: system.used_memory(),
+				total: system.total_memory(),
+			},
+			swap: Usage {
+				used: system.used_swap(),
+				total: system.total_swap(),
+			},
+			disks: disks
+				.iter()
+				.map(|disk| Disk {
+					name: disk.name().to_string_lossy().to_string(),
+					usage: Usage {
+						used: disk.total_space().saturating_sub(disk.available_space()),
+						total: disk.total_space(),
+					},
+				})
+				.collect(),
+			temperatures: components
+				.iter()
+				.map(|component| Temperature {
+					label: component.label().to_owned(),
+					value: component.temperature(),
+				})
+				.collect(),
+		},
+	}
+}
+
+pub async fn main(State(core): State<CorePtr>, Extension(auth): Extension<AuthPtr>) -> impl IntoResponse {
+	let Status { server, system } = collect(&core);
+	let blocked = auth.blocked();
 
 	let mut response = String::from("Server:\n");
-	response.push_str(&format!("  Version: {}\n", env!("CARGO_PKG_VERSION")));
+	response.push_str(&format!("  Version: {}\n", server.version));
 	response.push_str(&format!(
 		"  Uptime: {:#}\n",
-		SignedDuration::from_secs(core.start_time().elapsed().unwrap_or_default().as_secs() as i64)
+		SignedDuration::from_secs(server.uptime as i64)
 	));
 	response.push_str(&format!(
 		"  Start Time: {}\n",
-		util::timestamp(Some(*core.start_time()))
+		util::timestamp(Some(SystemTime::from(server.start_time)))
 	));
 	response.push_str(&format!(
 		"  Running Programs: {}/{} ({})\n",
-		programs.iter().filter(|(_, program)| program.is_active()).count(),
-		programs.len(),
-		programs
-			.iter()
-			.filter(|(_, program)| program.is_active())
-			.map(|(name, _)| name.to_owned())
-			.collect::<Vec<_>>()
-			.join(", ")
+		server.running_programs.len(),
+		server.programs,
+		server.running_programs.join(", ")
+	));
+	response.push_str(&format!(
+		"  Blocked Addresses: {}\n",
+		if blocked.is_empty() {
+			String::from("None")
+		} else {
+			blocked
+				.iter()
+				.map(|(ip, remaining)| {
+					format!(
+						"{ip} ({:#} left)",
+						SignedDuration::from_secs(remaining.as_secs() as i64)
+					)
+				})
+				.collect::<Vec<_>>()
+				.join(", ")
+		}
 	));
 	response.push('\n');
 
 	response.push_str("System:\n");
 	response.push_str(&format!(
 		"  Version: {}\n",
-		System::long_os_version()
-			.or_else(System::os_version)
-			.unwrap_or_else(|| String::from("N/A"))
+		system.version.unwrap_or_else(|| String::from("N/A"))
 	));
 	response.push_str(&format!(
 		"  Uptime: {:#}\n",
-		SignedDuration::from_secs(System::uptime() as i64)
+		SignedDuration::from_secs(system.uptime as i64)
 	));
 	response.push_str(&format!(
 		"  Boot Time: {}\n",
-		util::timestamp(Some(SystemTime::UNIX_EPOCH + Duration::from_secs(System::boot_time())))
+		util::timestamp(Some(SystemTime::from(system.boot_time)))
 	));
-	response.push_str(&format!("  Processes: {}\n", system.processes().len()));
+	response.push_str(&format!("  Processes: {}\n", system.processes));
 	response.push('\n');
 
 	response.push_str("  CPU Load:\n");
 	response.push_str(
 		&system
-			.cpus()
+			.cpus
 			.iter()
 			.map(|cpu| {
 				format!(
 					"    {}: {:.2}% ({:.2} GHz)\n",
-					cpu.name(),
-					cpu.cpu_usage(),
-					cpu.frequency() as f64 / 1000.0
+					cpu.name,
+					cpu.usage,
+					cpu.frequency as f64 / 1000.0
 				)
 			})
 			.collect::<Vec<_>>()
@@ -77,39 +214,35 @@ pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
 	);
 	response.push_str(&format!(
 		"    Total: {:.2}% ({:.2} GHz)\n",
-		system.cpus().iter().map(|cpu| cpu.cpu_usage()).sum::<f32>() / system.cpus().len() as f32,
-		system
-			.cpus()
-			.iter()
-			.map(|cpu| cpu.frequency() as f64 / 1000.0)
-			.sum::<f64>()
-			/ system.cpus().len() as f64
+		system.cpus.iter().map(|cpu| cpu.usage).sum::<f32>() / system.cpus.len() as f32,
+		system.cpus.iter().map(|cpu| cpu.frequency as f64 / 1000.0).sum::<f64>() / system.cpus.len() as f64
 	));
 	response.push('\n');
 
 	response.push_str("  RAM Usage:\n");
 	response.push_str(&format!(
 		"    Memory: {:.2} / {:.2} GB\n",
-		system.used_memory() as f64 / GIGABYTE,
-		system.total_memory() as f64 / GIGABYTE
+		system.memory.used as f64 / GIGABYTE,
+		system.memory.total as f64 / GIGABYTE
 	));
 	response.push_str(&format!(
 		"    Swap: {:.2} / {:.2} GB\n",
-		system.used_swap() as f64 / GIGABYTE,
-		system.total_swap() as f64 / GIGABYTE
+		system.swap.used as f64 / GIGABYTE,
+		system.swap.total as f64 / GIGABYTE
 	));
 	response.push('\n');
 
 	response.push_str("  Disk Usage:\n");
 	response.push_str(
-		&disks
+		&system
+			.disks
 			.iter()
 			.map(|disk| {
 				format!(
 					"    {}: {:.2} / {:.2} GB\n",
-					disk.name().to_string_lossy(),
-					(disk.total_space() - disk.available_space()) as f64 / GIGABYTE,
-					disk.total_space() as f64 / GIGABYTE
+					disk.name,
+					disk.usage.used as f64 / GIGABYTE,
+					disk.usage.total as f64 / GIGABYTE
 				)
 			})
 			.collect::<Vec<_>>()
@@ -117,20 +250,21 @@ pub async fn main(State(core): State<CorePtr>) -> impl IntoResponse {
 	);
 	response.push_str(&format!(
 		"    Total: {:.2} / {:.2} GB\n",
-		(total_space - available_space) as f64 / GIGABYTE,
-		total_space as f64 / GIGABYTE
+		system.disks.iter().map(|disk| disk.usage.used).sum::<u64>() as f64 / GIGABYTE,
+		system.disks.iter().map(|disk| disk.usage.total).sum::<u64>() as f64 / GIGABYTE
 	));
 	response.push('\n');
 
 	response.push_str("  Temperatures:\n");
 	response.push_str(
-		&components
+		&system
+			.temperatures
 			.iter()
-			.map(|component| {
+			.map(|temperature| {
 				format!(
 					"    {}: {:.2} °C\n",
-					component.label(),
-					component.temperature().unwrap_or(0.0)
+					temperature.label,
+					temperature.value.unwrap_or(0.0)
 				)
 			})
 			.collect::<Vec<_>>()
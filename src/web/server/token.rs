@@ -0,0 +1,57 @@
+use axum::{Form, extract::State, response::IntoResponse};
+use serde::Deserialize;
+
+use crate::{logger::Table, response, tokens::Scope, util, web::middleware::auth::AuthPtr};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRequest {
+	name: String,
+	scope: Scope,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeRequest {
+	name: String,
+}
+
+pub async fn create(State(auth): State<AuthPtr>, Form(request): Form<CreateRequest>) -> impl IntoResponse {
+	match auth.create_token(&request.name, request.scope) {
+		Ok(value) => response!(
+			OK,
+			"Token {} created with the {} scope, it will not be shown again:\n{value}",
+			request.name,
+			request.scope
+		),
+		Err(err) => response!(BAD_REQUEST, "{err}"),
+	}
+}
+
+pub async fn list(State(auth): State<AuthPtr>) -> impl IntoResponse {
+	let tokens = auth.tokens();
+
+	if tokens.is_empty() {
+		return response!(NOT_FOUND, "There are no API tokens on the server");
+	}
+
+	let mut table = Table::new();
+	table.set_header(vec!["Name", "ID", "Scope", "Created"]);
+
+	for (name, token) in tokens {
+		table.add_row(vec![
+			name,
+			token.id,
+			token.scope.to_string(),
+			util::timestamp(Some(token.created.into())),
+		]);
+	}
+
+	response!(OK, table.to_string())
+}
+
+pub async fn revoke(State(auth): State<AuthPtr>, Form(request): Form<RevokeRequest>) -> impl IntoResponse {
+	match auth.revoke_token(&request.name) {
+		Ok(true) => response!(OK, "Token {} revoked successfully", request.name),
+		Ok(false) => response!(NOT_FOUND, "Token {} does not exist", request.name),
+		Err(err) => response!(INTERNAL_SERVER_ERROR, "{err}"),
+	}
+}